@@ -1,17 +1,37 @@
+mod exec;
 mod file;
+mod grpc;
 mod http;
+mod postgres;
+mod redis;
 mod tcp;
+#[cfg(unix)]
+mod unix;
 
+pub use exec::ExecCheck;
 pub use file::FileCheck;
+pub use grpc::GrpcCheck;
 pub use http::{HttpCheck, build_http_client};
+pub use postgres::PostgresCheck;
+pub use redis::RedisCheck;
 pub use tcp::TcpCheck;
+#[cfg(unix)]
+pub use unix::UnixSocketCheck;
 
 use std::future::Future;
 use std::pin::Pin;
 
 pub type CheckFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+pub type ChangeFuture<'a> = Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>>;
 
 pub trait Check: Send + Sync {
     fn check(&self) -> CheckFuture<'_>;
     fn description(&self) -> &str;
+
+    // Resolves when the checked resource may have changed, so the wait phase
+    // can check again before its next poll. Resolves with a message when the
+    // change alone fails the check, such as a watched file being deleted.
+    fn changed(&self) -> ChangeFuture<'_> {
+        Box::pin(std::future::pending())
+    }
 }
@@ -0,0 +1,188 @@
+use super::{Check, CheckFuture};
+#[cfg(unix)]
+use crate::reaper;
+use std::io;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+const MAX_OUTPUT: usize = 1024;
+
+pub struct ExecCheck {
+    command: String,
+    description: String,
+    timeout: Duration,
+}
+
+impl ExecCheck {
+    pub fn new(command: String, timeout: Duration) -> Self {
+        let description = format!("exec:{command}");
+        Self {
+            command,
+            description,
+            timeout,
+        }
+    }
+}
+
+impl Check for ExecCheck {
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let mut command = shell(&self.command);
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+
+            let output = match run(&mut command, self.timeout).await {
+                Ok(Some(output)) => output,
+                Ok(None) => {
+                    return Err(format!(
+                        "exec `{}` timed out after {:?}",
+                        self.command, self.timeout
+                    ));
+                }
+                Err(e) => return Err(format!("exec `{}`: {e}", self.command)),
+            };
+
+            if output.status.success() {
+                return Ok(());
+            }
+
+            let status = match output.status.code() {
+                Some(code) => format!("exited with code {code}"),
+                None => "was terminated by a signal".to_string(),
+            };
+            match captured(&output) {
+                Some(text) => Err(format!("exec `{}` {status}: {text}", self.command)),
+                None => Err(format!("exec `{}` {status}", self.command)),
+            }
+        })
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+// In init mode every child is collected by the reaper, so the exit status has
+// to come from it rather than from waiting on the child directly.
+#[cfg(unix)]
+async fn run(command: &mut Command, limit: Duration) -> io::Result<Option<Output>> {
+    if !reaper::is_installed() {
+        command.kill_on_drop(true);
+        return timeout(limit, command.output()).await.ok().transpose();
+    }
+
+    // The reaper may collect the child before it is dropped, so killing it on
+    // drop could hit a reused pid.
+    let (mut child, status) = reaper::spawn(command)?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let output = async {
+        let (stdout, stderr) = tokio::join!(read(stdout), read(stderr));
+        let status = status
+            .await
+            .map_err(|_| io::Error::other("process status was lost"))?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    };
+
+    match timeout(limit, output).await {
+        Ok(output) => output.map(Some),
+        Err(_) => {
+            let _ = reaper::kill(&child);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn run(command: &mut Command, limit: Duration) -> io::Result<Option<Output>> {
+    command.kill_on_drop(true);
+    timeout(limit, command.output()).await.ok().transpose()
+}
+
+#[cfg(unix)]
+async fn read(pipe: Option<impl tokio::io::AsyncRead + Unpin>) -> Vec<u8> {
+    use tokio::io::AsyncReadExt;
+
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf).await;
+    }
+    buf
+}
+
+fn captured(output: &Output) -> Option<String> {
+    let text = [&output.stdout, &output.stderr]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.is_empty() {
+        return None;
+    }
+
+    if text.len() <= MAX_OUTPUT {
+        return Some(text);
+    }
+    let mut end = MAX_OUTPUT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(format!("{}...", &text[..end]))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn exec(command: &str) -> ExecCheck {
+        ExecCheck::new(command.to_string(), Duration::from_secs(5))
+    }
+
+    #[tokio::test]
+    async fn zero_exit_is_healthy() {
+        assert_eq!(exec("exit 0").check().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn failure_includes_exit_code_and_output() {
+        let err = exec("echo checking; echo 'no response' >&2; exit 2")
+            .check()
+            .await
+            .unwrap_err();
+
+        assert!(err.contains("exited with code 2"), "{err}");
+        assert!(err.contains("checking\nno response"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn slow_command_times_out() {
+        let check = ExecCheck::new("sleep 30".to_string(), Duration::from_millis(100));
+
+        let err = check.check().await.unwrap_err();
+
+        assert!(err.contains("timed out"), "{err}");
+    }
+}
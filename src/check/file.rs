@@ -1,31 +1,339 @@
+#[cfg(target_os = "linux")]
+use super::ChangeFuture;
 use super::{Check, CheckFuture};
-use std::path::PathBuf;
+use crate::config::{FileCheckConfig, FileKind};
+use std::fs::Metadata;
+use std::io;
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncReadExt;
+
+const MAX_CONTENT: u64 = 64 * 1024;
 
 pub struct FileCheck {
-    path: PathBuf,
+    config: FileCheckConfig,
     description: String,
+    #[cfg(target_os = "linux")]
+    watcher: Option<watcher::Watcher>,
+}
+
+impl FileCheck {
+    pub fn new(config: FileCheckConfig) -> Self {
+        let description = format!("file:{}", config.path);
+        #[cfg(target_os = "linux")]
+        let watcher = match watcher::Watcher::new(std::path::Path::new(&config.path)) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::debug!(
+                    "not watching {} for changes, polling only: {e}",
+                    config.path
+                );
+                None
+            }
+        };
+        Self {
+            config,
+            description,
+            #[cfg(target_os = "linux")]
+            watcher,
+        }
+    }
+
+    async fn probe(&self) -> Result<(), String> {
+        let path = &self.config.path;
+        let expect = &self.config.expect;
+        let metadata = match tokio::fs::metadata(path).await {
+            Ok(_) if expect.absent => return Err(format!("file {path} still exists")),
+            Ok(metadata) => metadata,
+            Err(e) if expect.absent && e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if expect.absent => return Err(format!("file {path}: {e}")),
+            Err(_) => return Err(format!("file {path} does not exist")),
+        };
+
+        if let Some(kind) = expect.kind
+            && !is_kind(&metadata, kind)
+        {
+            return Err(format!("file {path} is not {kind}"));
+        }
+
+        if let Some(max_age) = expect.max_age {
+            let modified = metadata
+                .modified()
+                .map_err(|e| format!("file {path}: modification time: {e}"))?;
+            // A modification time in the future counts as fresh.
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > max_age {
+                return Err(format!(
+                    "file {path} is stale: last modified {:?} ago, max age {max_age:?}",
+                    Duration::from_millis(age.as_millis() as u64)
+                ));
+            }
+        }
+
+        if expect.non_empty && metadata.len() == 0 {
+            return Err(format!("file {path} is empty"));
+        }
+
+        if expect.contains.is_none() && expect.matches.is_none() {
+            return Ok(());
+        }
+        let content = read_content(path)
+            .await
+            .map_err(|e| format!("file {path}: {e}"))?;
+        if let Some(text) = &expect.contains
+            && !content.contains(text.as_str())
+        {
+            return Err(format!("file {path} does not contain {text:?}"));
+        }
+        if let Some(pattern) = &expect.matches
+            && !pattern.is_match(&content)
+        {
+            return Err(format!("file {path} does not match {pattern}"));
+        }
+        Ok(())
+    }
 }
 
+#[cfg(target_os = "linux")]
 impl FileCheck {
-    pub fn new(path: impl Into<PathBuf>) -> Self {
-        let path = path.into();
-        let description = format!("file:{}", path.display());
-        Self { path, description }
+    // Only a file that disappeared, or appeared when it should be absent,
+    // fails on an event; everything else is left to the next poll.
+    async fn existence_failure(&self) -> Option<String> {
+        let path = &self.config.path;
+        match tokio::fs::metadata(path).await {
+            Ok(_) if self.config.expect.absent => Some(format!("file {path} still exists")),
+            Err(e) if !self.config.expect.absent && e.kind() == io::ErrorKind::NotFound => {
+                Some(format!("file {path} does not exist"))
+            }
+            _ => None,
+        }
     }
 }
 
 impl Check for FileCheck {
     fn check(&self) -> CheckFuture<'_> {
-        Box::pin(async move {
-            if tokio::fs::metadata(&self.path).await.is_ok() {
-                Ok(())
-            } else {
-                Err(format!("file {} does not exist", self.path.display()))
-            }
-        })
+        Box::pin(self.probe())
     }
 
     fn description(&self) -> &str {
         &self.description
     }
+
+    #[cfg(target_os = "linux")]
+    fn changed(&self) -> ChangeFuture<'_> {
+        match &self.watcher {
+            Some(watcher) => Box::pin(async move {
+                watcher.changed().await;
+                self.existence_failure().await
+            }),
+            None => Box::pin(std::future::pending()),
+        }
+    }
+}
+
+// Watches the parent directory, since the file itself may not exist yet or
+// may be replaced by a rename.
+#[cfg(target_os = "linux")]
+mod watcher {
+    use futures::StreamExt;
+    use inotify::{EventStream, Inotify, WatchMask};
+    use std::ffi::OsString;
+    use std::io;
+    use std::path::Path;
+    use tokio::sync::Mutex;
+
+    pub struct Watcher {
+        name: OsString,
+        events: Mutex<EventStream<[u8; 1024]>>,
+    }
+
+    impl Watcher {
+        pub fn new(path: &Path) -> io::Result<Self> {
+            let name = path
+                .file_name()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+
+            let inotify = Inotify::init()?;
+            inotify.watches().add(
+                dir,
+                WatchMask::CREATE
+                    | WatchMask::DELETE
+                    | WatchMask::MOVED_FROM
+                    | WatchMask::MOVED_TO
+                    | WatchMask::CLOSE_WRITE
+                    | WatchMask::ATTRIB
+                    | WatchMask::DELETE_SELF
+                    | WatchMask::MOVE_SELF,
+            )?;
+            Ok(Self {
+                name: name.to_os_string(),
+                events: Mutex::new(inotify.into_event_stream([0; 1024])?),
+            })
+        }
+
+        pub async fn changed(&self) {
+            let mut events = self.events.lock().await;
+            while let Some(Ok(event)) = events.next().await {
+                if event.name.is_none_or(|name| name == self.name) {
+                    return;
+                }
+            }
+            // The stream ended or failed; polling still covers the check.
+            std::future::pending().await
+        }
+    }
+}
+
+fn is_kind(metadata: &Metadata, kind: FileKind) -> bool {
+    match kind {
+        FileKind::File => metadata.is_file(),
+        FileKind::Dir => metadata.is_dir(),
+        #[cfg(unix)]
+        FileKind::Socket => std::os::unix::fs::FileTypeExt::is_socket(&metadata.file_type()),
+        #[cfg(not(unix))]
+        FileKind::Socket => false,
+    }
+}
+
+async fn read_content(path: &str) -> io::Result<String> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut content = Vec::new();
+    file.take(MAX_CONTENT).read_to_end(&mut content).await?;
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{FileExpect, Schedule};
+    use regex::Regex;
+    use std::path::{Path, PathBuf};
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("watchctl-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn check(path: &Path, expect: FileExpect) -> FileCheck {
+        FileCheck::new(FileCheckConfig {
+            path: path.display().to_string(),
+            expect,
+            schedule: Schedule::for_test(),
+        })
+    }
+
+    #[tokio::test]
+    async fn stale_heartbeat_fails() {
+        let path = temp_file("heartbeat", "");
+        let fresh = FileExpect {
+            max_age: Some(Duration::from_secs(30)),
+            ..FileExpect::default()
+        };
+        assert_eq!(check(&path, fresh).check().await, Ok(()));
+
+        let old = SystemTime::now() - Duration::from_secs(120);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let stale = FileExpect {
+            max_age: Some(Duration::from_secs(30)),
+            ..FileExpect::default()
+        };
+        let err = check(&path, stale).check().await.unwrap_err();
+        assert!(err.contains("is stale: last modified 120"), "{err}");
+        assert!(err.ends_with("max age 30s"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn checks_content() {
+        let empty = temp_file("empty", "");
+        let non_empty = FileExpect {
+            non_empty: true,
+            ..FileExpect::default()
+        };
+        let err = check(&empty, non_empty).check().await.unwrap_err();
+        assert!(err.ends_with("is empty"), "{err}");
+
+        let status = temp_file("status", "state=ready\n");
+        let ready = FileExpect {
+            matches: Some(Regex::new("state=ready").unwrap()),
+            ..FileExpect::default()
+        };
+        assert_eq!(check(&status, ready).check().await, Ok(()));
+
+        let failed = FileExpect {
+            matches: Some(Regex::new("state=failed").unwrap()),
+            ..FileExpect::default()
+        };
+        let err = check(&status, failed).check().await.unwrap_err();
+        assert!(err.ends_with("does not match state=failed"), "{err}");
+
+        let contains = FileExpect {
+            contains: Some("ready".to_string()),
+            ..FileExpect::default()
+        };
+        assert_eq!(check(&status, contains).check().await, Ok(()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn reports_changes_to_the_file() {
+        let path = temp_file("watched", "");
+        std::fs::remove_file(&path).unwrap();
+        let check = check(&path, FileExpect::default());
+
+        let other = path.with_file_name("unrelated");
+        std::fs::write(&other, "").unwrap();
+        let changed = tokio::time::timeout(Duration::from_millis(100), check.changed()).await;
+        assert!(changed.is_err(), "unrelated file should not wake the check");
+
+        std::fs::write(&path, "").unwrap();
+        let change = tokio::time::timeout(Duration::from_secs(5), check.changed())
+            .await
+            .expect("creating the file should wake the check");
+        assert_eq!(change, None);
+        assert_eq!(check.check().await, Ok(()));
+
+        std::fs::remove_file(&path).unwrap();
+        let change = tokio::time::timeout(Duration::from_secs(5), check.changed())
+            .await
+            .expect("deleting the file should wake the check");
+        assert!(change.unwrap().ends_with("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn checks_absence_and_type() {
+        let lock = temp_file("migrate.lock", "");
+        let absent = || FileExpect {
+            absent: true,
+            ..FileExpect::default()
+        };
+        let err = check(&lock, absent()).check().await.unwrap_err();
+        assert!(err.ends_with("still exists"), "{err}");
+        std::fs::remove_file(&lock).unwrap();
+        assert_eq!(check(&lock, absent()).check().await, Ok(()));
+
+        let dir = lock.parent().unwrap();
+        let kind = |kind| FileExpect {
+            kind: Some(kind),
+            ..FileExpect::default()
+        };
+        assert_eq!(check(dir, kind(FileKind::Dir)).check().await, Ok(()));
+        let err = check(dir, kind(FileKind::Socket))
+            .check()
+            .await
+            .unwrap_err();
+        assert!(err.ends_with("is not a socket"), "{err}");
+    }
 }
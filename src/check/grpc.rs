@@ -0,0 +1,305 @@
+use super::{Check, CheckFuture};
+use crate::config::GrpcCheckConfig;
+use bytes::Bytes;
+use http::{HeaderMap, Request};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+const MAX_RESPONSE: usize = 64 * 1024;
+
+const UNKNOWN: u64 = 0;
+const SERVING: u64 = 1;
+const NOT_SERVING: u64 = 2;
+const SERVICE_UNKNOWN: u64 = 3;
+
+pub struct GrpcCheck {
+    config: GrpcCheckConfig,
+    description: String,
+}
+
+impl GrpcCheck {
+    pub fn new(config: GrpcCheckConfig) -> Self {
+        let description = match config.service.as_str() {
+            "" => format!("grpc:{}", config.addr),
+            service => format!("grpc:{}/{service}", config.addr),
+        };
+        Self {
+            config,
+            description,
+        }
+    }
+
+    async fn probe(&self) -> Result<(), String> {
+        let addr = &self.config.addr;
+        let tcp = TcpStream::connect(addr)
+            .await
+            .map_err(|e| format!("grpc connect to {addr}: {e}"))?;
+        let (client, connection) = h2::client::handshake(tcp)
+            .await
+            .map_err(|e| format!("grpc {addr}: HTTP/2 handshake: {e}"))?;
+        let connection = tokio::spawn(connection);
+
+        let result = self.call(client).await;
+        connection.abort();
+        result
+    }
+
+    async fn call(&self, client: h2::client::SendRequest<Bytes>) -> Result<(), String> {
+        let addr = &self.config.addr;
+        let mut client = client
+            .ready()
+            .await
+            .map_err(|e| format!("grpc {addr}: {e}"))?;
+
+        let request = Request::post(format!("http://{addr}{HEALTH_CHECK_PATH}"))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(())
+            .map_err(|e| format!("grpc {addr}: {e}"))?;
+        let (response, mut stream) = client
+            .send_request(request, false)
+            .map_err(|e| format!("grpc {addr}: {e}"))?;
+        stream
+            .send_data(frame(&request_message(&self.config.service)), true)
+            .map_err(|e| format!("grpc {addr}: {e}"))?;
+
+        let response = response.await.map_err(|e| format!("grpc {addr}: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "grpc {addr} returned HTTP status {}",
+                response.status()
+            ));
+        }
+        let headers = response.headers().clone();
+        let mut body = response.into_body();
+
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| format!("grpc {addr}: {e}"))?;
+            let _ = body.flow_control().release_capacity(chunk.len());
+            data.extend_from_slice(&chunk);
+            if data.len() > MAX_RESPONSE {
+                return Err(format!("grpc {addr}: response too large"));
+            }
+        }
+        let trailers = body
+            .trailers()
+            .await
+            .map_err(|e| format!("grpc {addr}: {e}"))?
+            .unwrap_or_default();
+
+        // A trailers-only response carries grpc-status in the headers.
+        let status = grpc_status(&trailers).or_else(|| grpc_status(&headers));
+        if let Some((code, message)) = status.filter(|(code, _)| *code != 0) {
+            return Err(format!(
+                "grpc {addr} health check failed with status {code}: {message}"
+            ));
+        }
+
+        let message = unframe(&data).ok_or_else(|| format!("grpc {addr}: malformed response"))?;
+        let service = match self.config.service.as_str() {
+            "" => "server".to_string(),
+            service => format!("service {service}"),
+        };
+        match serving_status(message) {
+            Some(SERVING) => Ok(()),
+            Some(NOT_SERVING) => Err(format!("grpc {addr} {service} is NOT_SERVING")),
+            Some(SERVICE_UNKNOWN) => Err(format!("grpc {addr} {service} is SERVICE_UNKNOWN")),
+            Some(UNKNOWN) => Err(format!("grpc {addr} {service} status is UNKNOWN")),
+            Some(other) => Err(format!("grpc {addr} {service} returned status {other}")),
+            None => Err(format!("grpc {addr}: malformed response")),
+        }
+    }
+}
+
+impl Check for GrpcCheck {
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            match timeout(self.config.schedule.timeout, self.probe()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("grpc {} timed out", self.config.addr)),
+            }
+        })
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+fn grpc_status(headers: &HeaderMap) -> Option<(u32, String)> {
+    let code = headers.get("grpc-status")?.to_str().ok()?.parse().ok()?;
+    let message = headers
+        .get("grpc-message")
+        .and_then(|m| m.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    Some((code, message))
+}
+
+// HealthCheckRequest { string service = 1; }
+fn request_message(service: &str) -> Vec<u8> {
+    if service.is_empty() {
+        return Vec::new();
+    }
+    let mut message = vec![0x0a];
+    put_varint(&mut message, service.len() as u64);
+    message.extend_from_slice(service.as_bytes());
+    message
+}
+
+// HealthCheckResponse { ServingStatus status = 1; }, where a missing field
+// means the proto3 default of UNKNOWN.
+fn serving_status(mut message: &[u8]) -> Option<u64> {
+    let mut status = UNKNOWN;
+    while !message.is_empty() {
+        let key = get_varint(&mut message)?;
+        match key & 0x7 {
+            0 => {
+                let value = get_varint(&mut message)?;
+                if key >> 3 == 1 {
+                    status = value;
+                }
+            }
+            2 => {
+                let len = usize::try_from(get_varint(&mut message)?).ok()?;
+                message = message.get(len..)?;
+            }
+            _ => return None,
+        }
+    }
+    Some(status)
+}
+
+fn frame(message: &[u8]) -> Bytes {
+    let mut framed = vec![0];
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    Bytes::from(framed)
+}
+
+fn unframe(data: &[u8]) -> Option<&[u8]> {
+    let (header, rest) = data.split_at_checked(5)?;
+    if header[0] != 0 {
+        return None;
+    }
+    let len = u32::from_be_bytes(header[1..5].try_into().ok()?) as usize;
+    rest.get(..len)
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn get_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Schedule;
+    use http::Response;
+    use tokio::net::TcpListener;
+
+    // Answers every health check with the given serving status, or with a
+    // trailers-only error when `grpc_status` is non-zero.
+    async fn serve(serving: u64, grpc_status: u32) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut connection = h2::server::handshake(socket).await.unwrap();
+                    while let Some(Ok((request, mut respond))) = connection.accept().await {
+                        assert_eq!(request.uri().path(), HEALTH_CHECK_PATH);
+                        let mut body = request.into_body();
+                        while body.data().await.is_some() {}
+
+                        let response = Response::builder()
+                            .header("content-type", "application/grpc")
+                            .body(())
+                            .unwrap();
+                        let mut trailers = HeaderMap::new();
+                        trailers.insert("grpc-status", grpc_status.into());
+                        if grpc_status != 0 {
+                            trailers.insert("grpc-message", "unknown service".parse().unwrap());
+                            let mut response = response;
+                            response.headers_mut().extend(trailers);
+                            respond.send_response(response, true).unwrap();
+                            continue;
+                        }
+
+                        let mut stream = respond.send_response(response, false).unwrap();
+                        let mut message = vec![0x08];
+                        put_varint(&mut message, serving);
+                        stream.send_data(frame(&message), false).unwrap();
+                        stream.send_trailers(trailers).unwrap();
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    fn check(addr: String, service: &str) -> GrpcCheck {
+        GrpcCheck::new(GrpcCheckConfig {
+            addr,
+            service: service.to_string(),
+            schedule: Schedule::for_test(),
+        })
+    }
+
+    #[tokio::test]
+    async fn serving_is_healthy() {
+        let addr = serve(SERVING, 0).await;
+
+        assert_eq!(check(addr, "").check().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn not_serving_and_unknown_are_distinct() {
+        let addr = serve(NOT_SERVING, 0).await;
+        let err = check(addr, "orders").check().await.unwrap_err();
+        assert!(err.ends_with("service orders is NOT_SERVING"), "{err}");
+
+        let addr = serve(UNKNOWN, 0).await;
+        let err = check(addr, "").check().await.unwrap_err();
+        assert!(err.ends_with("server status is UNKNOWN"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn grpc_error_status_is_reported() {
+        let addr = serve(SERVING, 5).await;
+
+        let err = check(addr, "missing").check().await.unwrap_err();
+
+        assert!(
+            err.ends_with("failed with status 5: unknown service"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn encodes_and_decodes_messages() {
+        assert_eq!(request_message("orders"), b"\x0a\x06orders");
+        assert_eq!(request_message(""), b"");
+        assert_eq!(serving_status(b"\x08\x02"), Some(NOT_SERVING));
+        assert_eq!(serving_status(b""), Some(UNKNOWN));
+        assert_eq!(serving_status(b"\x08"), None);
+    }
+}
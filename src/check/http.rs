@@ -1,41 +1,67 @@
 use super::{Check, CheckFuture};
-use reqwest::Client;
+use crate::config::{HttpCheckConfig, HttpExpect};
+use reqwest::{Client, Response};
 use std::sync::Arc;
-use std::time::Duration;
+
+const MAX_BODY: usize = 64 * 1024;
+const MAX_QUOTE: usize = 200;
 
 pub struct HttpCheck {
-    url: String,
+    config: HttpCheckConfig,
     description: String,
     client: Arc<Client>,
 }
 
 impl HttpCheck {
-    pub fn new(url: String, client: Arc<Client>) -> Self {
-        let description = format!("http:{url}");
+    pub fn new(config: HttpCheckConfig, client: Arc<Client>) -> Self {
+        let description = format!("http:{}", config.url);
         Self {
-            url,
+            config,
             description,
             client,
         }
     }
 }
 
-pub fn build_http_client(timeout: Duration) -> Result<Client, reqwest::Error> {
-    Client::builder().timeout(timeout).build()
+pub fn build_http_client() -> Result<Client, reqwest::Error> {
+    Client::builder().build()
 }
 
 impl Check for HttpCheck {
     fn check(&self) -> CheckFuture<'_> {
         Box::pin(async move {
-            match self.client.get(&self.url).send().await {
-                Ok(resp) if resp.status().is_success() => Ok(()),
-                Ok(resp) => Err(format!(
-                    "http {} returned status {}",
-                    self.url,
-                    resp.status()
-                )),
-                Err(e) => Err(format!("http {}: {e}", self.url)),
+            let config = &self.config;
+            let mut request = self
+                .client
+                .request(config.method.clone(), &config.url)
+                .timeout(config.schedule.timeout);
+            for (name, value) in &config.headers {
+                request = request.header(name, value);
             }
+            if let Some(body) = &config.body {
+                request = request.body(body.clone());
+            }
+
+            let resp = request
+                .send()
+                .await
+                .map_err(|e| format!("http {}: {e}", config.url))?;
+
+            let status = resp.status();
+            if !config.expect.status.contains(status.as_u16()) {
+                return Err(format!(
+                    "http {} returned status {status}, expected {}",
+                    config.url, config.expect.status
+                ));
+            }
+
+            if !has_body_assertions(&config.expect) {
+                return Ok(());
+            }
+            let body = read_body(resp)
+                .await
+                .map_err(|e| format!("http {}: reading body: {e}", config.url))?;
+            check_body(&config.expect, &body).map_err(|msg| format!("http {} {msg}", config.url))
         })
     }
 
@@ -43,3 +69,167 @@ impl Check for HttpCheck {
         &self.description
     }
 }
+
+// Body assertions only look at the first MAX_BODY bytes; the rest is never
+// read, so a huge or endless response cannot exhaust memory.
+async fn read_body(mut resp: Response) -> reqwest::Result<String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        let room = MAX_BODY - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() == MAX_BODY {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn has_body_assertions(expect: &HttpExpect) -> bool {
+    expect.contains.is_some() || expect.matches.is_some() || !expect.json.is_empty()
+}
+
+fn check_body(expect: &HttpExpect, body: &str) -> Result<(), String> {
+    if let Some(needle) = &expect.contains
+        && !body.contains(needle.as_str())
+    {
+        return Err(format!("body does not contain {needle:?}: {}", quote(body)));
+    }
+
+    if let Some(regex) = &expect.matches
+        && !regex.is_match(body)
+    {
+        return Err(format!("body does not match /{regex}/: {}", quote(body)));
+    }
+
+    if expect.json.is_empty() {
+        return Ok(());
+    }
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| format!("body is not valid JSON ({e}): {}", quote(body)))?;
+    for (pointer, expected) in &expect.json {
+        match json.pointer(pointer) {
+            Some(actual) if actual == expected => {}
+            Some(actual) => {
+                return Err(format!("json {pointer} is {actual}, expected {expected}"));
+            }
+            None => return Err(format!("json {pointer} is missing: {}", quote(body))),
+        }
+    }
+    Ok(())
+}
+
+fn quote(body: &str) -> String {
+    let body = body.trim();
+    if body.len() <= MAX_QUOTE {
+        return format!("{body:?}");
+    }
+    let mut end = MAX_QUOTE;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{:?}...", &body[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Schedule, StatusSet};
+    use regex::Regex;
+    use reqwest::Method;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    async fn serve(status: &'static str, body: impl Into<String>) -> String {
+        let body = body.into();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 4096];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}/health")
+    }
+
+    fn check(url: String, expect: HttpExpect) -> HttpCheck {
+        let config = HttpCheckConfig {
+            url,
+            method: Method::GET,
+            headers: Vec::new(),
+            body: None,
+            expect,
+            schedule: Schedule::for_test(),
+        };
+        HttpCheck::new(config, Arc::new(build_http_client().unwrap()))
+    }
+
+    #[tokio::test]
+    async fn accepts_configured_status() {
+        let url = serve("503 Service Unavailable", "").await;
+
+        let err = check(url.clone(), HttpExpect::default())
+            .check()
+            .await
+            .unwrap_err();
+        assert!(err.contains("expected 200-299"), "{err}");
+
+        let expect = HttpExpect {
+            status: StatusSet(vec![503..=503]),
+            ..HttpExpect::default()
+        };
+        assert_eq!(check(url, expect).check().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn json_mismatch_quotes_actual_value() {
+        let url = serve("200 OK", r#"{"status":"degraded","replicas":3}"#).await;
+        let expect = HttpExpect {
+            json: vec![
+                ("/replicas".to_string(), serde_json::json!(3)),
+                ("/status".to_string(), serde_json::json!("ok")),
+            ],
+            ..HttpExpect::default()
+        };
+
+        let err = check(url, expect).check().await.unwrap_err();
+
+        assert!(
+            err.ends_with(r#"json /status is "degraded", expected "ok""#),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn body_assertions_read_only_the_first_64kb() {
+        let url = serve("200 OK", format!("{}ready", "x".repeat(MAX_BODY))).await;
+        let expect = HttpExpect {
+            contains: Some("ready".to_string()),
+            ..HttpExpect::default()
+        };
+
+        let err = check(url, expect).check().await.unwrap_err();
+
+        assert!(err.contains("body does not contain \"ready\""), "{err}");
+    }
+
+    #[test]
+    fn body_assertions_quote_the_body() {
+        let expect = HttpExpect {
+            contains: Some("up".to_string()),
+            matches: Some(Regex::new(r"^ok\b").unwrap()),
+            ..HttpExpect::default()
+        };
+
+        assert_eq!(check_body(&expect, "ok: all up"), Ok(()));
+        let err = check_body(&expect, "down").unwrap_err();
+        assert_eq!(err, r#"body does not contain "up": "down""#);
+        let err = check_body(&expect, "not ok: up").unwrap_err();
+        assert!(err.starts_with(r"body does not match /^ok\b/"), "{err}");
+    }
+}
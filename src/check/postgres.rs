@@ -0,0 +1,212 @@
+use super::{Check, CheckFuture};
+use crate::config::PostgresCheckConfig;
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const PROTOCOL_VERSION: i32 = 3 << 16;
+const MAX_MESSAGE: usize = 64 * 1024;
+const CANNOT_CONNECT_NOW: &str = "57P03";
+
+pub struct PostgresCheck {
+    config: PostgresCheckConfig,
+    description: String,
+}
+
+impl PostgresCheck {
+    pub fn new(config: PostgresCheckConfig) -> Self {
+        let description = format!(
+            "postgres:{}:{}/{}",
+            config.host, config.port, config.database
+        );
+        Self {
+            config,
+            description,
+        }
+    }
+
+    // Like pg_isready: the server is ready once it answers the startup
+    // message with an authentication request or any error other than
+    // "cannot connect now" (starting up, shutting down, in recovery).
+    async fn probe(&self) -> Result<(), String> {
+        let addr = format!("{}:{}", self.config.host, self.config.port);
+        let mut stream = TcpStream::connect(&addr)
+            .await
+            .map_err(|e| format!("postgres connect to {addr}: {e}"))?;
+
+        stream
+            .write_all(&startup_message(&self.config.user, &self.config.database))
+            .await
+            .map_err(|e| format!("postgres {addr}: sending startup message: {e}"))?;
+
+        let (kind, body) = read_message(&mut stream)
+            .await
+            .map_err(|e| format!("postgres {addr}: reading response: {e}"))?;
+
+        match kind {
+            b'R' | b'v' => Ok(()),
+            b'E' => {
+                let (code, message) = error_fields(&body);
+                if code == CANNOT_CONNECT_NOW {
+                    Err(format!("postgres {addr}: {message}"))
+                } else {
+                    Ok(())
+                }
+            }
+            other => Err(format!(
+                "postgres {addr} sent unexpected message type {:?}",
+                other as char
+            )),
+        }
+    }
+}
+
+impl Check for PostgresCheck {
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            match timeout(self.config.schedule.timeout, self.probe()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!(
+                    "postgres {}:{} timed out",
+                    self.config.host, self.config.port
+                )),
+            }
+        })
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+fn startup_message(user: &str, database: &str) -> Vec<u8> {
+    let mut body = PROTOCOL_VERSION.to_be_bytes().to_vec();
+    for (key, value) in [
+        ("user", user),
+        ("database", database),
+        ("application_name", "watchctl"),
+    ] {
+        body.extend_from_slice(key.as_bytes());
+        body.push(0);
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+    }
+    body.push(0);
+
+    let mut message = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+async fn read_message(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let kind = stream.read_u8().await?;
+    let len = stream.read_i32().await?;
+    let len = usize::try_from(len)
+        .ok()
+        .and_then(|len| len.checked_sub(4))
+        .filter(|len| *len <= MAX_MESSAGE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid message length"))?;
+
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body).await?;
+    Ok((kind, body))
+}
+
+// ErrorResponse fields are a type byte followed by a NUL-terminated string.
+fn error_fields(body: &[u8]) -> (String, String) {
+    let mut code = String::new();
+    let mut message = String::new();
+    for field in body.split(|b| *b == 0).filter(|f| !f.is_empty()) {
+        let value = String::from_utf8_lossy(&field[1..]).into_owned();
+        match field[0] {
+            b'C' => code = value,
+            b'M' => message = value,
+            _ => {}
+        }
+    }
+    (code, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Schedule;
+    use tokio::net::TcpListener;
+
+    fn error_response(code: &str, message: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (field, value) in [(b'S', "FATAL"), (b'C', code), (b'M', message)] {
+            body.push(field);
+            body.extend_from_slice(value.as_bytes());
+            body.push(0);
+        }
+        body.push(0);
+
+        let mut message = vec![b'E'];
+        message.extend_from_slice(&((body.len() + 4) as i32).to_be_bytes());
+        message.extend_from_slice(&body);
+        message
+    }
+
+    async fn serve(response: Vec<u8>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let len = socket.read_i32().await.unwrap();
+                let mut startup = vec![0; len as usize - 4];
+                socket.read_exact(&mut startup).await.unwrap();
+                assert_eq!(&startup[..4], &PROTOCOL_VERSION.to_be_bytes());
+                assert!(startup.windows(9).any(|w| w == b"user\0app\0"));
+                let _ = socket.write_all(&response).await;
+            }
+        });
+        port
+    }
+
+    fn check(port: u16) -> PostgresCheck {
+        PostgresCheck::new(PostgresCheckConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            user: "app".to_string(),
+            database: "app".to_string(),
+            schedule: Schedule::for_test(),
+        })
+    }
+
+    #[tokio::test]
+    async fn starting_up_is_not_ready() {
+        let port = serve(error_response(
+            CANNOT_CONNECT_NOW,
+            "the database system is starting up",
+        ))
+        .await;
+
+        let err = check(port).check().await.unwrap_err();
+
+        assert!(
+            err.ends_with(": the database system is starting up"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn authentication_request_is_ready() {
+        let md5_request = vec![b'R', 0, 0, 0, 12, 0, 0, 0, 5, 1, 2, 3, 4];
+        let port = serve(md5_request).await;
+
+        assert_eq!(check(port).check().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn authentication_failure_is_ready() {
+        let port = serve(error_response(
+            "28P01",
+            "password authentication failed for user \"app\"",
+        ))
+        .await;
+
+        assert_eq!(check(port).check().await, Ok(()));
+    }
+}
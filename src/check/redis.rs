@@ -0,0 +1,166 @@
+use super::{Check, CheckFuture};
+use crate::config::RedisCheckConfig;
+use std::io;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const MAX_REPLY: u64 = 4096;
+
+pub struct RedisCheck {
+    config: RedisCheckConfig,
+    description: String,
+}
+
+impl RedisCheck {
+    pub fn new(config: RedisCheckConfig) -> Self {
+        let description = format!("redis:{}:{}", config.host, config.port);
+        Self {
+            config,
+            description,
+        }
+    }
+
+    async fn probe(&self) -> Result<(), String> {
+        let addr = format!("{}:{}", self.config.host, self.config.port);
+        let stream = TcpStream::connect(&addr)
+            .await
+            .map_err(|e| format!("redis connect to {addr}: {e}"))?;
+        let mut stream = BufReader::new(stream);
+
+        if let Some(password) = &self.config.password {
+            let mut auth = vec!["AUTH"];
+            if let Some(username) = &self.config.username {
+                auth.push(username);
+            }
+            auth.push(password);
+            match command(&mut stream, &auth).await {
+                Ok(reply) if reply == "+OK" => {}
+                Ok(reply) => return Err(format!("redis {addr}: AUTH failed: {}", error(&reply))),
+                Err(e) => return Err(format!("redis {addr}: {e}")),
+            }
+        }
+
+        let reply = command(&mut stream, &["PING"])
+            .await
+            .map_err(|e| format!("redis {addr}: {e}"))?;
+        match reply.as_str() {
+            "+PONG" => Ok(()),
+            _ if reply.starts_with("-LOADING") => {
+                Err(format!("redis {addr} is loading the dataset into memory"))
+            }
+            _ => Err(format!("redis {addr}: PING returned {}", error(&reply))),
+        }
+    }
+}
+
+impl Check for RedisCheck {
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            match timeout(self.config.schedule.timeout, self.probe()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!(
+                    "redis {}:{} timed out",
+                    self.config.host, self.config.port
+                )),
+            }
+        })
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+// Commands go out as RESP arrays of bulk strings; only the first line of the
+// reply matters for AUTH and PING.
+async fn command(stream: &mut BufReader<TcpStream>, args: &[&str]) -> io::Result<String> {
+    let mut request = format!("*{}\r\n", args.len());
+    for arg in args {
+        request.push_str(&format!("${}\r\n{arg}\r\n", arg.len()));
+    }
+    stream.get_mut().write_all(request.as_bytes()).await?;
+
+    let mut reply = String::new();
+    let n = (&mut *stream).take(MAX_REPLY).read_line(&mut reply).await?;
+    if n == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed before reply",
+        ));
+    }
+    Ok(reply.trim_end().to_string())
+}
+
+fn error(reply: &str) -> String {
+    match reply.strip_prefix('-') {
+        Some(message) => message.to_string(),
+        None => format!("{reply:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Schedule;
+    use tokio::net::TcpListener;
+
+    async fn serve(replies: &'static [&'static str]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let mut socket = BufReader::new(socket);
+                for reply in replies {
+                    let mut line = String::new();
+                    socket.read_line(&mut line).await.unwrap();
+                    let count: usize = line.trim()[1..].parse().unwrap();
+                    for _ in 0..count * 2 {
+                        line.clear();
+                        socket.read_line(&mut line).await.unwrap();
+                    }
+                    let _ = socket.get_mut().write_all(reply.as_bytes()).await;
+                }
+            }
+        });
+        port
+    }
+
+    fn check(port: u16, password: Option<&str>) -> RedisCheck {
+        RedisCheck::new(RedisCheckConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: None,
+            password: password.map(String::from),
+            schedule: Schedule::for_test(),
+        })
+    }
+
+    #[tokio::test]
+    async fn pong_is_ready() {
+        let port = serve(&["+OK\r\n", "+PONG\r\n"]).await;
+
+        assert_eq!(check(port, Some("secret")).check().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn loading_is_not_ready() {
+        let port = serve(&["-LOADING Redis is loading the dataset in memory\r\n"]).await;
+
+        let err = check(port, None).check().await.unwrap_err();
+
+        assert!(err.ends_with("is loading the dataset into memory"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn auth_failure_is_reported() {
+        let port = serve(&["-WRONGPASS invalid username-password pair\r\n"]).await;
+
+        let err = check(port, Some("nope")).check().await.unwrap_err();
+
+        assert!(
+            err.ends_with("AUTH failed: WRONGPASS invalid username-password pair"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use super::{Check, CheckFuture};
+use crate::config::UnixCheckConfig;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::time::timeout;
+
+const MAX_STATUS_LINE: usize = 1024;
+
+pub struct UnixSocketCheck {
+    config: UnixCheckConfig,
+    description: String,
+}
+
+impl UnixSocketCheck {
+    pub fn new(config: UnixCheckConfig) -> Self {
+        let description = match &config.http {
+            Some(path) => format!("unix:{}{path}", config.path),
+            None => format!("unix:{}", config.path),
+        };
+        Self {
+            config,
+            description,
+        }
+    }
+
+    async fn probe(&self) -> Result<(), String> {
+        let path = &self.config.path;
+        let mut stream = UnixStream::connect(path)
+            .await
+            .map_err(|e| format!("unix connect to {path}: {e}"))?;
+
+        let Some(target) = &self.config.http else {
+            return Ok(());
+        };
+
+        let request =
+            format!("GET {target} HTTP/1.0\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| format!("unix {path}: sending request: {e}"))?;
+
+        let line = read_status_line(&mut stream)
+            .await
+            .map_err(|e| format!("unix {path}: reading response: {e}"))?;
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .filter(|_| line.starts_with("HTTP/"))
+            .ok_or_else(|| format!("unix {path}{target} returned an invalid response: {line:?}"))?;
+
+        if !self.config.status.contains(status) {
+            return Err(format!(
+                "unix {path}{target} returned status {status}, expected {}",
+                self.config.status
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Check for UnixSocketCheck {
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            match timeout(self.config.schedule.timeout, self.probe()).await {
+                Ok(result) => result,
+                Err(_) => Err(format!("unix {} timed out", self.config.path)),
+            }
+        })
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+async fn read_status_line(stream: &mut UnixStream) -> std::io::Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 256];
+    while !buf.windows(2).any(|w| w == b"\r\n") && buf.len() < MAX_STATUS_LINE {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    Ok(text.lines().next().unwrap_or_default().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Schedule, StatusSet};
+    use std::path::{Path, PathBuf};
+    use tokio::net::UnixListener;
+
+    fn socket_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("watchctl-unix-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn check(path: &Path, http: Option<&str>) -> UnixSocketCheck {
+        UnixSocketCheck::new(UnixCheckConfig {
+            path: path.display().to_string(),
+            http: http.map(String::from),
+            status: StatusSet::default(),
+            schedule: Schedule::for_test(),
+        })
+    }
+
+    fn serve(path: &Path, response: &'static str) {
+        let listener = UnixListener::bind(path).unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn connects_to_listening_socket() {
+        let path = socket_path("connect.sock");
+
+        let err = check(&path, None).check().await.unwrap_err();
+        assert!(err.starts_with("unix connect to"), "{err}");
+
+        serve(&path, "");
+        assert_eq!(check(&path, None).check().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn checks_http_status_over_socket() {
+        let ok = socket_path("ok.sock");
+        serve(&ok, "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK");
+        assert_eq!(check(&ok, Some("/_ping")).check().await, Ok(()));
+
+        let down = socket_path("down.sock");
+        serve(&down, "HTTP/1.1 500 Internal Server Error\r\n\r\n");
+        let err = check(&down, Some("/_ping")).check().await.unwrap_err();
+        assert!(
+            err.ends_with("/_ping returned status 500, expected 200-299"),
+            "{err}"
+        );
+    }
+}
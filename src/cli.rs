@@ -1,3 +1,4 @@
+use crate::error::EXIT_ERROR;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
@@ -18,11 +19,10 @@ pub struct Args {
     #[arg(
         long = "wait-tcp-timeout",
         value_name = "DURATION",
-        default_value = "5s",
         help_heading = "Wait Phase",
-        help = "Per-attempt timeout for each --wait-tcp check"
+        help = "Per-attempt timeout for each --wait-tcp check [default: --wait-check-timeout]"
     )]
-    pub wait_tcp_timeout: String,
+    pub wait_tcp_timeout: Option<String>,
 
     #[arg(
         long = "wait-http",
@@ -36,11 +36,10 @@ pub struct Args {
     #[arg(
         long = "wait-http-timeout",
         value_name = "DURATION",
-        default_value = "5s",
         help_heading = "Wait Phase",
-        help = "Per-request timeout for each --wait-http check"
+        help = "Per-request timeout for each --wait-http check [default: --wait-check-timeout]"
     )]
-    pub wait_http_timeout: String,
+    pub wait_http_timeout: Option<String>,
 
     #[arg(
         long = "wait-file",
@@ -51,6 +50,59 @@ pub struct Args {
     )]
     pub wait_file: Vec<String>,
 
+    #[arg(
+        long = "wait-exec",
+        value_name = "COMMAND",
+        action = clap::ArgAction::Append,
+        help_heading = "Wait Phase",
+        help = "Wait until this shell command exits 0 (repeatable)"
+    )]
+    pub wait_exec: Vec<String>,
+
+    #[arg(
+        long = "wait-unix",
+        value_name = "PATH",
+        action = clap::ArgAction::Append,
+        help_heading = "Wait Phase",
+        help = "Wait until this Unix socket accepts a connection (repeatable)"
+    )]
+    pub wait_unix: Vec<String>,
+
+    #[arg(
+        long = "wait-postgres",
+        value_name = "URL",
+        action = clap::ArgAction::Append,
+        help_heading = "Wait Phase",
+        help = "Wait until this PostgreSQL server accepts connections, e.g. postgres://user@db:5432/app (repeatable)"
+    )]
+    pub wait_postgres: Vec<String>,
+
+    #[arg(
+        long = "wait-redis",
+        value_name = "URL",
+        action = clap::ArgAction::Append,
+        help_heading = "Wait Phase",
+        help = "Wait until this Redis server answers PING, e.g. redis://:password@cache:6379 (repeatable)"
+    )]
+    pub wait_redis: Vec<String>,
+
+    #[arg(
+        long = "wait-grpc",
+        value_name = "HOST:PORT[/SERVICE]",
+        action = clap::ArgAction::Append,
+        help_heading = "Wait Phase",
+        help = "Wait until this gRPC health service reports SERVING (repeatable)"
+    )]
+    pub wait_grpc: Vec<String>,
+
+    #[arg(
+        long = "wait-check-timeout",
+        value_name = "DURATION",
+        help_heading = "Wait Phase",
+        help = "Per-attempt timeout for every wait check without its own timeout [default: 5s]"
+    )]
+    pub wait_check_timeout: Option<String>,
+
     #[arg(
         long = "wait-delay",
         value_name = "DURATION",
@@ -63,11 +115,10 @@ pub struct Args {
     #[arg(
         long = "wait-timeout",
         value_name = "DURATION",
-        default_value = "30s",
         help_heading = "Wait Phase",
-        help = "Maximum total time for the wait phase"
+        help = "Maximum total time for the wait phase [default: 30s]"
     )]
-    pub wait_timeout: String,
+    pub wait_timeout: Option<String>,
 
     // WATCH PHASE
     #[arg(
@@ -82,20 +133,18 @@ pub struct Args {
     #[arg(
         long = "watch-http-interval",
         value_name = "DURATION",
-        default_value = "10s",
         help_heading = "Watch Phase",
-        help = "Interval between --watch-http checks (must be > 0)"
+        help = "Interval between --watch-http checks (must be > 0) [default: --watch-interval]"
     )]
-    pub watch_http_interval: String,
+    pub watch_http_interval: Option<String>,
 
     #[arg(
         long = "watch-http-timeout",
         value_name = "DURATION",
-        default_value = "5s",
         help_heading = "Watch Phase",
-        help = "Per-request timeout for --watch-http checks"
+        help = "Per-request timeout for --watch-http checks [default: --watch-check-timeout]"
     )]
-    pub watch_http_timeout: String,
+    pub watch_http_timeout: Option<String>,
 
     #[arg(
         long = "watch-tcp",
@@ -109,20 +158,18 @@ pub struct Args {
     #[arg(
         long = "watch-tcp-interval",
         value_name = "DURATION",
-        default_value = "10s",
         help_heading = "Watch Phase",
-        help = "Interval between --watch-tcp checks (must be > 0)"
+        help = "Interval between --watch-tcp checks (must be > 0) [default: --watch-interval]"
     )]
-    pub watch_tcp_interval: String,
+    pub watch_tcp_interval: Option<String>,
 
     #[arg(
         long = "watch-tcp-timeout",
         value_name = "DURATION",
-        default_value = "5s",
         help_heading = "Watch Phase",
-        help = "Per-attempt timeout for --watch-tcp checks"
+        help = "Per-attempt timeout for --watch-tcp checks [default: --watch-check-timeout]"
     )]
-    pub watch_tcp_timeout: String,
+    pub watch_tcp_timeout: Option<String>,
 
     #[arg(
         long = "watch-file",
@@ -133,14 +180,101 @@ pub struct Args {
     )]
     pub watch_file: Vec<String>,
 
+    #[arg(
+        long = "watch-file-fresh",
+        value_name = "PATH:MAXAGE",
+        action = clap::ArgAction::Append,
+        help_heading = "Watch Phase",
+        help = "Monitor that this file was modified within MAXAGE, e.g. /tmp/heartbeat:30s (repeatable)"
+    )]
+    pub watch_file_fresh: Vec<String>,
+
     #[arg(
         long = "watch-file-interval",
         value_name = "DURATION",
-        default_value = "10s",
         help_heading = "Watch Phase",
-        help = "Interval between --watch-file checks (must be > 0)"
+        help = "Interval between --watch-file checks (must be > 0) [default: --watch-interval]"
     )]
-    pub watch_file_interval: String,
+    pub watch_file_interval: Option<String>,
+
+    #[arg(
+        long = "watch-exec",
+        value_name = "COMMAND",
+        action = clap::ArgAction::Append,
+        help_heading = "Watch Phase",
+        help = "Run this shell command periodically while command runs; non-zero exit fails (repeatable)"
+    )]
+    pub watch_exec: Vec<String>,
+
+    #[arg(
+        long = "watch-unix",
+        value_name = "PATH",
+        action = clap::ArgAction::Append,
+        help_heading = "Watch Phase",
+        help = "Monitor this Unix socket while command runs (repeatable)"
+    )]
+    pub watch_unix: Vec<String>,
+
+    #[arg(
+        long = "watch-postgres",
+        value_name = "URL",
+        action = clap::ArgAction::Append,
+        help_heading = "Watch Phase",
+        help = "Monitor this PostgreSQL server while command runs (repeatable)"
+    )]
+    pub watch_postgres: Vec<String>,
+
+    #[arg(
+        long = "watch-redis",
+        value_name = "URL",
+        action = clap::ArgAction::Append,
+        help_heading = "Watch Phase",
+        help = "Monitor this Redis server while command runs (repeatable)"
+    )]
+    pub watch_redis: Vec<String>,
+
+    #[arg(
+        long = "watch-grpc",
+        value_name = "HOST:PORT[/SERVICE]",
+        action = clap::ArgAction::Append,
+        help_heading = "Watch Phase",
+        help = "Monitor this gRPC health service while command runs; anything but SERVING fails (repeatable)"
+    )]
+    pub watch_grpc: Vec<String>,
+
+    #[arg(
+        long = "watch-interval",
+        value_name = "DURATION",
+        help_heading = "Watch Phase",
+        help = "Interval between runs of every watch check without its own interval (must be > 0) [default: 10s]"
+    )]
+    pub watch_interval: Option<String>,
+
+    #[arg(
+        long = "watch-check-timeout",
+        value_name = "DURATION",
+        help_heading = "Watch Phase",
+        help = "Per-attempt timeout for every watch check without its own timeout [default: 5s]"
+    )]
+    pub watch_check_timeout: Option<String>,
+
+    #[arg(
+        long = "watch-failure-threshold",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help_heading = "Watch Phase",
+        help = "Consecutive failures of a watch check before the watch phase fails [default: 1]"
+    )]
+    pub watch_failure_threshold: Option<u32>,
+
+    #[arg(
+        long = "watch-success-threshold",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help_heading = "Watch Phase",
+        help = "Consecutive successes of a watch check needed to reset its failure count [default: 1]"
+    )]
+    pub watch_success_threshold: Option<u32>,
 
     #[arg(
         long = "watch-delay",
@@ -150,6 +284,31 @@ pub struct Args {
     )]
     pub watch_delay: Option<String>,
 
+    #[arg(
+        long = "ready-pattern",
+        value_name = "REGEX",
+        help_heading = "Watch Phase",
+        help = "Start watch health checks once a line of the command's output matches REGEX",
+        conflicts_with = "watch_delay"
+    )]
+    pub ready_pattern: Option<String>,
+
+    #[arg(
+        long = "fail-pattern",
+        value_name = "REGEX",
+        help_heading = "Watch Phase",
+        help = "Fail the watch phase when a line of the command's output matches REGEX"
+    )]
+    pub fail_pattern: Option<String>,
+
+    #[arg(
+        long = "watch-output-idle",
+        value_name = "DURATION",
+        help_heading = "Watch Phase",
+        help = "Fail the watch phase when the command prints nothing to stdout or stderr for DURATION"
+    )]
+    pub watch_output_idle: Option<String>,
+
     #[arg(
         long = "watch-timeout",
         value_name = "DURATION",
@@ -170,25 +329,82 @@ pub struct Args {
     #[arg(
         long = "retry-delay",
         value_name = "DURATION",
-        default_value = "1s",
         help_heading = "Retry Phase",
-        help = "Delay before each retry attempt"
+        help = "Delay before each retry attempt [default: 1s]"
     )]
-    pub retry_delay: String,
+    pub retry_delay: Option<String>,
 
     #[arg(
         long = "retry-backoff",
         help_heading = "Retry Phase",
-        help = "Double retry delay after each retry (max 5m)"
+        help = "Double retry delay after each retry (same as --retry-strategy exponential)",
+        conflicts_with = "retry_strategy"
     )]
     pub retry_backoff: bool,
 
+    #[arg(
+        long = "retry-strategy",
+        value_name = "STRATEGY",
+        help_heading = "Retry Phase",
+        help = "How the retry delay grows: constant, linear, exponential, decorrelated [default: constant]"
+    )]
+    pub retry_strategy: Option<String>,
+
+    #[arg(
+        long = "retry-factor",
+        value_name = "FACTOR",
+        help_heading = "Retry Phase",
+        help = "Multiplier for the exponential strategy [default: 2]"
+    )]
+    pub retry_factor: Option<f64>,
+
+    #[arg(
+        long = "retry-max-delay",
+        value_name = "DURATION",
+        help_heading = "Retry Phase",
+        help = "Upper bound for the growing retry delay [default: 5m]"
+    )]
+    pub retry_max_delay: Option<String>,
+
+    #[arg(
+        long = "retry-jitter",
+        value_name = "JITTER",
+        help_heading = "Retry Phase",
+        help = "Randomize each retry delay: none, full (0 to delay), equal (delay/2 to delay) [default: none]"
+    )]
+    pub retry_jitter: Option<String>,
+
+    #[arg(
+        long = "retry-reset-after",
+        value_name = "DURATION",
+        help_heading = "Retry Phase",
+        help = "Restore the retry budget and delay after an attempt runs this long"
+    )]
+    pub retry_reset_after: Option<String>,
+
+    #[arg(
+        long = "retry-limit-burst",
+        value_name = "N",
+        help_heading = "Retry Phase",
+        help = "Give up when there would be more than N restarts within --retry-limit-interval"
+    )]
+    pub retry_limit_burst: Option<u32>,
+
+    #[arg(
+        long = "retry-limit-interval",
+        value_name = "DURATION",
+        help_heading = "Retry Phase",
+        help = "Sliding window for --retry-limit-burst [default: 10s]",
+        requires = "retry_limit_burst"
+    )]
+    pub retry_limit_interval: Option<String>,
+
     #[arg(
         long = "retry-if",
         value_name = "CODES",
         action = clap::ArgAction::Append,
         help_heading = "Retry Phase",
-        help = "Retry only when exit code matches (e.g. 1,2,3); repeatable",
+        help = "Retry only when the exit matches: codes, ranges or signals (e.g. 1,64-78,SIGKILL,signal:*); repeatable",
         conflicts_with = "retry_except"
     )]
     pub retry_if: Vec<String>,
@@ -198,7 +414,7 @@ pub struct Args {
         value_name = "CODES",
         action = clap::ArgAction::Append,
         help_heading = "Retry Phase",
-        help = "Retry on any non-zero exit except these codes (e.g. 78,77); repeatable",
+        help = "Retry on any non-zero exit except these codes, ranges or signals (e.g. 78,77,SIGTERM); repeatable",
         conflicts_with = "retry_if"
     )]
     pub retry_except: Vec<String>,
@@ -206,10 +422,111 @@ pub struct Args {
     #[arg(
         long = "retry-with-wait",
         help_heading = "Retry Phase",
-        help = "Run the wait phase again before each retry"
+        help = "Run the wait phase again before each retry",
+        overrides_with = "no_retry_with_wait"
     )]
     pub retry_with_wait: bool,
 
+    #[arg(
+        long = "no-retry-with-wait",
+        help_heading = "Retry Phase",
+        help = "Do not re-run the wait phase before retries, even if the config file enables it",
+        overrides_with = "retry_with_wait"
+    )]
+    pub no_retry_with_wait: bool,
+
+    #[arg(
+        long = "retry-on",
+        value_name = "OUTCOMES",
+        action = clap::ArgAction::Append,
+        help_heading = "Retry Phase",
+        help = "Outcomes that trigger a retry: exit, health, timeout, idle, all (e.g. exit,health); repeatable [default: exit]"
+    )]
+    pub retry_on: Vec<String>,
+
+    // PROCESS
+    #[arg(
+        long = "stop-signal",
+        value_name = "SIGNAL",
+        help_heading = "Process",
+        help = "Signal sent to stop the command on health check failure or watch timeout [default: SIGTERM]"
+    )]
+    pub stop_signal: Option<String>,
+
+    #[arg(
+        long = "stop-timeout",
+        value_name = "DURATION",
+        help_heading = "Process",
+        help = "Grace period after the stop signal before sending SIGKILL [default: 10s]"
+    )]
+    pub stop_timeout: Option<String>,
+
+    #[arg(
+        long = "init",
+        help_heading = "Process",
+        help = "Reap orphaned processes like an init system (enabled automatically as PID 1)",
+        overrides_with = "no_init"
+    )]
+    pub init: bool,
+
+    #[arg(
+        long = "no-init",
+        help_heading = "Process",
+        help = "Do not reap orphaned processes unless running as PID 1, even if the config file enables it",
+        overrides_with = "init"
+    )]
+    pub no_init: bool,
+
+    #[arg(
+        long = "process-group",
+        help_heading = "Process",
+        help = "Start the command in its own process group and signal the whole group",
+        overrides_with = "no_process_group"
+    )]
+    pub process_group: bool,
+
+    #[arg(
+        long = "no-process-group",
+        help_heading = "Process",
+        help = "Keep the command in watchctl's process group, even if the config file sets process-group",
+        overrides_with = "process_group"
+    )]
+    pub no_process_group: bool,
+
+    #[arg(
+        long = "new-session",
+        help_heading = "Process",
+        help = "Start the command in a new session (implies --process-group)",
+        overrides_with = "no_new_session"
+    )]
+    pub new_session: bool,
+
+    #[arg(
+        long = "no-new-session",
+        help_heading = "Process",
+        help = "Keep the command in watchctl's session, even if the config file sets new-session",
+        overrides_with = "new_session"
+    )]
+    pub no_new_session: bool,
+
+    #[arg(
+        long = "exit-code",
+        value_name = "NAME=CODE",
+        action = clap::ArgAction::Append,
+        help_heading = "Process",
+        help = "Exit with CODE for a watchctl failure: wait-timeout, health, idle, watch-timeout, restart-limit, retries-exhausted; repeatable"
+    )]
+    pub exit_code: Vec<String>,
+
+    // CONFIG
+    #[arg(
+        long = "config",
+        value_name = "FILE",
+        help_heading = "Config",
+        help = "Read settings from a TOML file; command-line options take precedence"
+    )]
+    pub config: Option<String>,
+
     // LOGGING
     #[arg(
         long = "log",
@@ -222,7 +539,7 @@ pub struct Args {
     // COMMAND
     #[arg(
         last = true,
-        required = true,
+        required_unless_present = "config",
         help_heading = "Command",
         help = "Command and arguments to run (pass after --)"
     )]
@@ -230,5 +547,12 @@ pub struct Args {
 }
 
 pub fn parse() -> Args {
-    Args::parse()
+    Args::try_parse().unwrap_or_else(|e| {
+        // clap exits usage errors with 2, which the command could exit with too.
+        if e.use_stderr() {
+            let _ = e.print();
+            std::process::exit(EXIT_ERROR.into());
+        }
+        e.exit()
+    })
 }
@@ -0,0 +1,208 @@
+use super::spec::{
+    Entry, ExecSpec, FileSpec, GrpcSpec, HttpSpec, PostgresSpec, RedisSpec, TcpSpec, UnixSpec,
+};
+use crate::duration::parse_duration;
+use crate::error::{Error, Result};
+use crate::signal::{Signal, parse_signal};
+use regex::Regex;
+use serde::Deserialize;
+use serde::de::{self, Deserializer};
+use std::time::Duration;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FileConfig {
+    pub command: Option<Vec<String>>,
+    #[serde(default)]
+    pub wait: WaitSection,
+    #[serde(default)]
+    pub watch: WatchSection,
+    #[serde(default)]
+    pub retry: RetrySection,
+    #[serde(default)]
+    pub process: ProcessSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WaitSection {
+    #[serde(default)]
+    pub tcp: Vec<Entry<TcpSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub tcp_timeout: Option<Duration>,
+    #[serde(default)]
+    pub http: Vec<Entry<HttpSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub http_timeout: Option<Duration>,
+    #[serde(default)]
+    pub file: Vec<Entry<FileSpec>>,
+    #[serde(default)]
+    pub exec: Vec<Entry<ExecSpec>>,
+    #[serde(default)]
+    pub unix: Vec<Entry<UnixSpec>>,
+    #[serde(default)]
+    pub postgres: Vec<Entry<PostgresSpec>>,
+    #[serde(default)]
+    pub redis: Vec<Entry<RedisSpec>>,
+    #[serde(default)]
+    pub grpc: Vec<Entry<GrpcSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub check_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "durations")]
+    pub delay: Vec<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WatchSection {
+    #[serde(default)]
+    pub http: Vec<Entry<HttpSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub http_interval: Option<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub http_timeout: Option<Duration>,
+    #[serde(default)]
+    pub tcp: Vec<Entry<TcpSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub tcp_interval: Option<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub tcp_timeout: Option<Duration>,
+    #[serde(default)]
+    pub file: Vec<Entry<FileSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub file_interval: Option<Duration>,
+    #[serde(default)]
+    pub exec: Vec<Entry<ExecSpec>>,
+    #[serde(default)]
+    pub unix: Vec<Entry<UnixSpec>>,
+    #[serde(default)]
+    pub postgres: Vec<Entry<PostgresSpec>>,
+    #[serde(default)]
+    pub redis: Vec<Entry<RedisSpec>>,
+    #[serde(default)]
+    pub grpc: Vec<Entry<GrpcSpec>>,
+    #[serde(default, deserialize_with = "duration")]
+    pub interval: Option<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub check_timeout: Option<Duration>,
+    pub failure_threshold: Option<u32>,
+    pub success_threshold: Option<u32>,
+    #[serde(default, deserialize_with = "duration")]
+    pub delay: Option<Duration>,
+    #[serde(default, deserialize_with = "pattern")]
+    pub ready_pattern: Option<Regex>,
+    #[serde(default, deserialize_with = "pattern")]
+    pub fail_pattern: Option<Regex>,
+    #[serde(default, deserialize_with = "duration")]
+    pub output_idle: Option<Duration>,
+    #[serde(default, deserialize_with = "duration")]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RetrySection {
+    pub times: Option<u32>,
+    #[serde(default, deserialize_with = "duration")]
+    pub delay: Option<Duration>,
+    #[serde(default)]
+    pub backoff: bool,
+    pub strategy: Option<String>,
+    pub factor: Option<f64>,
+    #[serde(default, deserialize_with = "duration")]
+    pub max_delay: Option<Duration>,
+    pub jitter: Option<String>,
+    #[serde(default, deserialize_with = "duration")]
+    pub reset_after: Option<Duration>,
+    pub limit_burst: Option<u32>,
+    #[serde(default, deserialize_with = "duration")]
+    pub limit_interval: Option<Duration>,
+    #[serde(default, rename = "if", deserialize_with = "codes")]
+    pub only: Vec<String>,
+    #[serde(default, deserialize_with = "codes")]
+    pub except: Vec<String>,
+    #[serde(default)]
+    pub on: Vec<String>,
+    #[serde(default)]
+    pub with_wait: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProcessSection {
+    #[serde(default, deserialize_with = "signal")]
+    pub stop_signal: Option<Signal>,
+    #[serde(default, deserialize_with = "duration")]
+    pub stop_timeout: Option<Duration>,
+    #[serde(default)]
+    pub init: bool,
+    #[serde(default)]
+    pub process_group: bool,
+    #[serde(default)]
+    pub new_session: bool,
+    #[serde(default)]
+    pub exit_code: Vec<String>,
+}
+
+pub fn load(path: &str) -> Result<FileConfig> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| Error::Config(format!("{path}: {e}")))?;
+    parse(&content).map_err(|e| match e {
+        Error::Config(msg) => Error::Config(format!("{path}: {msg}")),
+        other => other,
+    })
+}
+
+pub fn parse(content: &str) -> Result<FileConfig> {
+    toml::from_str(content).map_err(|e| Error::Config(e.to_string().trim_end().to_string()))
+}
+
+fn duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Duration>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    parse_duration(&raw).map(Some).map_err(de::Error::custom)
+}
+
+fn durations<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<Duration>, D::Error> {
+    let raw = Vec::<String>::deserialize(deserializer)?;
+    raw.iter()
+        .map(|s| parse_duration(s).map_err(de::Error::custom))
+        .collect()
+}
+
+fn pattern<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Regex>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    Regex::new(&raw).map(Some).map_err(de::Error::custom)
+}
+
+fn signal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Signal>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    parse_signal(&raw).map(Some).map_err(de::Error::custom)
+}
+
+fn codes<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Code {
+        Number(i64),
+        Text(String),
+    }
+
+    let raw = Vec::<Code>::deserialize(deserializer)?;
+    Ok(raw
+        .into_iter()
+        .map(|code| match code {
+            Code::Number(n) => n.to_string(),
+            Code::Text(s) => s,
+        })
+        .collect())
+}
@@ -0,0 +1,704 @@
+use super::{FileExpect, FileKind, HttpExpect, StatusSet};
+use crate::duration::parse_duration;
+use crate::error::{Error, Result};
+use regex::Regex;
+use reqwest::Method;
+use serde::Deserialize;
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+use toml::Value;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Settings {
+    pub timeout: Option<Duration>,
+    pub interval: Option<Duration>,
+    pub failure_threshold: Option<u32>,
+    pub success_threshold: Option<u32>,
+}
+
+impl Settings {
+    const KEYS: &[&str] = &[
+        "timeout",
+        "interval",
+        "threshold",
+        "failure-threshold",
+        "success-threshold",
+    ];
+
+    fn apply(&mut self, key: &str, value: &Value) -> Result<bool> {
+        match key {
+            "timeout" => self.timeout = Some(duration(key, value)?),
+            "interval" => self.interval = Some(duration(key, value)?),
+            "threshold" | "failure-threshold" => self.failure_threshold = Some(count(key, value)?),
+            "success-threshold" => self.success_threshold = Some(count(key, value)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+pub trait Spec: Sized {
+    const TARGET: &'static str;
+    // Options of this kind beyond the shared settings.
+    const KEYS: &'static [&'static str] = &[];
+
+    fn new(target: String) -> Self;
+    fn settings(&mut self) -> &mut Settings;
+
+    fn apply(&mut self, key: &str, _value: &Value) -> Result<()> {
+        Err(Error::InvalidCheckOption(format!("unknown option {key}")))
+    }
+}
+
+#[derive(Debug)]
+pub struct TcpSpec {
+    pub addr: String,
+    pub settings: Settings,
+}
+
+impl Spec for TcpSpec {
+    const TARGET: &'static str = "addr";
+
+    fn new(addr: String) -> Self {
+        Self {
+            addr,
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+#[derive(Debug)]
+pub struct HttpSpec {
+    pub url: String,
+    pub method: Method,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub expect: HttpExpect,
+    pub settings: Settings,
+}
+
+impl Spec for HttpSpec {
+    const TARGET: &'static str = "url";
+    const KEYS: &'static [&'static str] = &[
+        "header", "method", "body", "status", "contains", "matches", "json",
+    ];
+
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            method: Method::GET,
+            headers: Vec::new(),
+            body: None,
+            expect: HttpExpect::default(),
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    fn apply(&mut self, key: &str, value: &Value) -> Result<()> {
+        match (key, value) {
+            ("header", Value::String(header)) => {
+                let (name, value) = header.split_once(':').ok_or_else(|| {
+                    Error::InvalidCheckOption(format!("header: expected NAME: VALUE, got {header}"))
+                })?;
+                self.headers
+                    .push((name.trim().to_string(), value.trim().to_string()));
+            }
+            ("headers", Value::Table(table)) => {
+                for (name, value) in table {
+                    self.headers.push((name.clone(), string(name, value)?));
+                }
+            }
+            ("header", _) => return Err(invalid(key, "a string")),
+            ("headers", _) => return Err(invalid(key, "a table")),
+            ("method", _) => {
+                let method = string(key, value)?.to_ascii_uppercase();
+                self.method = Method::from_bytes(method.as_bytes())
+                    .map_err(|_| invalid(key, "an HTTP method"))?;
+            }
+            ("body", _) => self.body = Some(string(key, value)?),
+            ("status", _) => self.expect.status = status_set(key, value)?,
+            ("contains", _) => self.expect.contains = Some(string(key, value)?),
+            ("matches", _) => {
+                let pattern = string(key, value)?;
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| Error::InvalidCheckOption(format!("{key}: {e}")))?;
+                self.expect.matches = Some(regex);
+            }
+            ("json", Value::Table(table)) => {
+                for (pointer, expected) in table {
+                    let expected = serde_json::to_value(expected)
+                        .map_err(|e| Error::InvalidCheckOption(format!("{key}: {e}")))?;
+                    self.expect
+                        .json
+                        .push((json_pointer(key, pointer)?, expected));
+                }
+            }
+            ("json", Value::Array(items)) => {
+                for item in items {
+                    self.expect
+                        .json
+                        .push(json_assertion(key, &string(key, item)?)?);
+                }
+            }
+            ("json", _) => self
+                .expect
+                .json
+                .push(json_assertion(key, &string(key, value)?)?),
+            _ => return Err(Error::InvalidCheckOption(format!("unknown option {key}"))),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct FileSpec {
+    pub path: String,
+    pub expect: FileExpect,
+    pub settings: Settings,
+}
+
+impl Spec for FileSpec {
+    const TARGET: &'static str = "path";
+    const KEYS: &'static [&'static str] = &[
+        "absent",
+        "non-empty",
+        "type",
+        "max-age",
+        "contains",
+        "matches",
+    ];
+
+    fn new(path: String) -> Self {
+        Self {
+            path,
+            expect: FileExpect::default(),
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    fn apply(&mut self, key: &str, value: &Value) -> Result<()> {
+        match (key, value) {
+            ("absent", _) => self.expect.absent = boolean(key, value)?,
+            ("non-empty", _) => self.expect.non_empty = boolean(key, value)?,
+            ("type", _) => {
+                self.expect.kind = Some(match string(key, value)?.as_str() {
+                    "file" => FileKind::File,
+                    "dir" => FileKind::Dir,
+                    "socket" => FileKind::Socket,
+                    _ => return Err(invalid(key, "file, dir or socket")),
+                })
+            }
+            ("max-age", _) => self.expect.max_age = Some(duration(key, value)?),
+            ("contains", _) => self.expect.contains = Some(string(key, value)?),
+            ("matches", _) => {
+                let pattern = string(key, value)?;
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| Error::InvalidCheckOption(format!("{key}: {e}")))?;
+                self.expect.matches = Some(regex);
+            }
+            _ => return Err(Error::InvalidCheckOption(format!("unknown option {key}"))),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ExecSpec {
+    pub command: String,
+    pub settings: Settings,
+}
+
+impl Spec for ExecSpec {
+    const TARGET: &'static str = "command";
+
+    fn new(command: String) -> Self {
+        Self {
+            command,
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+#[derive(Debug)]
+pub struct UnixSpec {
+    pub path: String,
+    pub http: Option<String>,
+    pub status: StatusSet,
+    pub settings: Settings,
+}
+
+impl Spec for UnixSpec {
+    const TARGET: &'static str = "path";
+    const KEYS: &'static [&'static str] = &["http", "status"];
+
+    fn new(path: String) -> Self {
+        Self {
+            path,
+            http: None,
+            status: StatusSet::default(),
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    fn apply(&mut self, key: &str, value: &Value) -> Result<()> {
+        match (key, value) {
+            ("http", Value::Boolean(true)) => self.http = Some("/".to_string()),
+            ("http", _) => {
+                let path = string(key, value)?;
+                if !path.starts_with('/') {
+                    return Err(invalid(key, "a request path such as /_ping"));
+                }
+                self.http = Some(path);
+            }
+            ("status", _) => self.status = status_set(key, value)?,
+            _ => return Err(Error::InvalidCheckOption(format!("unknown option {key}"))),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresSpec {
+    pub url: String,
+    pub settings: Settings,
+}
+
+impl Spec for PostgresSpec {
+    const TARGET: &'static str = "url";
+
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+#[derive(Debug)]
+pub struct RedisSpec {
+    pub url: String,
+    pub settings: Settings,
+}
+
+impl Spec for RedisSpec {
+    const TARGET: &'static str = "url";
+
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+#[derive(Debug)]
+pub struct GrpcSpec {
+    pub addr: String,
+    pub settings: Settings,
+}
+
+impl Spec for GrpcSpec {
+    const TARGET: &'static str = "addr";
+
+    fn new(addr: String) -> Self {
+        Self {
+            addr,
+            settings: Settings::default(),
+        }
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+fn set<T: Spec>(spec: &mut T, key: &str, value: &Value) -> Result<()> {
+    if spec.settings().apply(key, value)? {
+        return Ok(());
+    }
+    spec.apply(key, value)
+}
+
+// Inline form: TARGET;key=value;key=value
+pub fn parse_inline<T: Spec>(raw: &str) -> Result<T> {
+    let (target, options) = split(raw, T::KEYS);
+    let target = target.trim();
+    if target.is_empty() {
+        return Err(Error::InvalidCheckOption(format!(
+            "{raw}: missing {}",
+            T::TARGET
+        )));
+    }
+
+    let mut spec = T::new(target.to_string());
+    for part in options.into_iter().map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = match part.split_once('=') {
+            Some((key, value)) => (key.trim(), Value::String(value.trim().to_string())),
+            None => (part, Value::Boolean(true)),
+        };
+        set(&mut spec, key, &value)
+            .map_err(|e| Error::InvalidCheckOption(format!("{raw}: {}", error_message(e))))?;
+    }
+    Ok(spec)
+}
+
+// Targets may contain ';' themselves, as URL parameters or between shell
+// commands, so only trailing segments that name a known option are taken off
+// the end.
+fn split<'a>(raw: &'a str, keys: &[&str]) -> (&'a str, Vec<&'a str>) {
+    let mut target = raw;
+    let mut options = Vec::new();
+    while let Some((rest, option)) = target.rsplit_once(';') {
+        let key = option.split_once('=').map_or(option, |(key, _)| key).trim();
+        if !key.is_empty() && !Settings::KEYS.contains(&key) && !keys.contains(&key) {
+            break;
+        }
+        options.push(option);
+        target = rest;
+    }
+    options.reverse();
+    (target, options)
+}
+
+fn error_message(error: Error) -> String {
+    match error {
+        Error::InvalidCheckOption(msg) => msg,
+        other => other.to_string(),
+    }
+}
+
+// A check in the config file is written either in the inline form
+// ("db:5432;timeout=1s") or as a table ({ addr = "db:5432", timeout = "1s" }).
+#[derive(Debug)]
+pub struct Entry<T>(pub T);
+
+impl<'de, T: Spec> Deserialize<'de> for Entry<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct EntryVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Spec> Visitor<'de> for EntryVisitor<T> {
+            type Value = Entry<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string or a table")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Entry<T>, E> {
+                parse_inline(v).map(Entry).map_err(E::custom)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Entry<T>, A::Error> {
+                let mut target = None;
+                let mut options = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    let value: Value = map.next_value()?;
+                    if key == T::TARGET {
+                        target = Some(string(&key, &value).map_err(de::Error::custom)?);
+                    } else {
+                        options.push((key, value));
+                    }
+                }
+
+                let target = target.ok_or_else(|| de::Error::missing_field(T::TARGET))?;
+                let mut spec = T::new(target);
+                for (key, value) in &options {
+                    set(&mut spec, key, value).map_err(de::Error::custom)?;
+                }
+                Ok(Entry(spec))
+            }
+        }
+
+        deserializer.deserialize_any(EntryVisitor(PhantomData))
+    }
+}
+
+fn invalid(key: &str, expected: &str) -> Error {
+    Error::InvalidCheckOption(format!("{key}: expected {expected}"))
+}
+
+fn string(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(invalid(key, "a string")),
+    }
+}
+
+// Inline values are strings, so "true" and "false" are accepted as well.
+fn boolean(key: &str, value: &Value) -> Result<bool> {
+    match value {
+        Value::Boolean(b) => Ok(*b),
+        Value::String(s) if s == "true" => Ok(true),
+        Value::String(s) if s == "false" => Ok(false),
+        _ => Err(invalid(key, "a boolean")),
+    }
+}
+
+fn duration(key: &str, value: &Value) -> Result<Duration> {
+    match value {
+        Value::String(s) => {
+            parse_duration(s).map_err(|e| Error::InvalidCheckOption(format!("{key}: {e}")))
+        }
+        _ => Err(invalid(key, "a duration such as \"5s\"")),
+    }
+}
+
+// Accepts "200", "200,204", "500-599" and "2xx", alone or combined.
+fn status_set(key: &str, value: &Value) -> Result<StatusSet> {
+    let items = match value {
+        Value::Array(items) => items.clone(),
+        other => vec![other.clone()],
+    };
+
+    let mut ranges = Vec::new();
+    for item in &items {
+        let raw = match item {
+            Value::Integer(n) => n.to_string(),
+            Value::String(s) => s.clone(),
+            _ => {
+                return Err(invalid(
+                    key,
+                    "status codes such as \"200,204\" or \"500-599\"",
+                ));
+            }
+        };
+        for part in raw.split(',').map(str::trim) {
+            ranges.push(status_range(part).ok_or_else(|| {
+                Error::InvalidCheckOption(format!("{key}: invalid status {part:?}"))
+            })?);
+        }
+    }
+    Ok(StatusSet(ranges))
+}
+
+fn status_range(raw: &str) -> Option<std::ops::RangeInclusive<u16>> {
+    let code = |s: &str| {
+        s.trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|c| (100..=599).contains(c))
+    };
+
+    if let Some(class) = raw.strip_suffix("xx").or_else(|| raw.strip_suffix("XX")) {
+        let class: u16 = class.parse().ok().filter(|c| (1..=5).contains(c))?;
+        return Some(class * 100..=class * 100 + 99);
+    }
+    match raw.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (code(start)?, code(end)?);
+            (start <= end).then_some(start..=end)
+        }
+        None => code(raw).map(|c| c..=c),
+    }
+}
+
+// Inline form: POINTER=VALUE, where VALUE is compared as JSON when it parses
+// as JSON and as a plain string otherwise.
+fn json_assertion(key: &str, raw: &str) -> Result<(String, serde_json::Value)> {
+    let (pointer, expected) = raw
+        .split_once('=')
+        .ok_or_else(|| invalid(key, "POINTER=VALUE such as /status=ok"))?;
+    let expected = expected.trim();
+    let expected = serde_json::from_str(expected)
+        .unwrap_or_else(|_| serde_json::Value::String(expected.to_string()));
+    Ok((json_pointer(key, pointer)?, expected))
+}
+
+fn json_pointer(key: &str, pointer: &str) -> Result<String> {
+    let pointer = pointer.trim();
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(Error::InvalidCheckOption(format!(
+            "{key}: JSON pointer must start with '/', got {pointer}"
+        )));
+    }
+    Ok(pointer.to_string())
+}
+
+fn count(key: &str, value: &Value) -> Result<u32> {
+    match value {
+        Value::Integer(n) => u32::try_from(*n).map_err(|_| invalid(key, "a positive number")),
+        Value::String(s) => s.parse().map_err(|_| invalid(key, "a positive number")),
+        _ => Err(invalid(key, "a positive number")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inline_settings() {
+        let spec: HttpSpec =
+            parse_inline("http://x/health;interval=30s;timeout=2s;threshold=3").unwrap();
+
+        assert_eq!(spec.url, "http://x/health");
+        assert_eq!(spec.settings.interval, Some(Duration::from_secs(30)));
+        assert_eq!(spec.settings.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(spec.settings.failure_threshold, Some(3));
+        assert_eq!(spec.settings.success_threshold, None);
+    }
+
+    #[test]
+    fn parses_inline_headers() {
+        let spec: HttpSpec =
+            parse_inline("http://x/health;header=Authorization: Bearer a=b;header=X-Probe: 1")
+                .unwrap();
+
+        assert_eq!(
+            spec.headers,
+            vec![
+                ("Authorization".to_string(), "Bearer a=b".to_string()),
+                ("X-Probe".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_inline_http_expectations() {
+        let spec: HttpSpec = parse_inline(
+            "http://x/health;method=post;body={};status=200,204,5xx;contains=up;json=/status=ok;json=/replicas=3",
+        )
+        .unwrap();
+
+        assert_eq!(spec.method, Method::POST);
+        assert_eq!(spec.body.as_deref(), Some("{}"));
+        assert_eq!(spec.expect.status.to_string(), "200,204,500-599");
+        assert!(spec.expect.status.contains(503));
+        assert!(!spec.expect.status.contains(201));
+        assert_eq!(spec.expect.contains.as_deref(), Some("up"));
+        assert_eq!(
+            spec.expect.json,
+            vec![
+                ("/status".to_string(), serde_json::json!("ok")),
+                ("/replicas".to_string(), serde_json::json!(3)),
+            ]
+        );
+
+        assert!(parse_inline::<HttpSpec>("http://x;status=700").is_err());
+        assert!(parse_inline::<HttpSpec>("http://x;status=300-200").is_err());
+        assert!(parse_inline::<HttpSpec>("http://x;matches=(").is_err());
+        assert!(parse_inline::<HttpSpec>("http://x;json=status=ok").is_err());
+    }
+
+    #[test]
+    fn parses_inline_unix_http_probe() {
+        let spec: UnixSpec = parse_inline("/var/run/docker.sock;http=/_ping;status=200").unwrap();
+        assert_eq!(spec.path, "/var/run/docker.sock");
+        assert_eq!(spec.http.as_deref(), Some("/_ping"));
+        assert_eq!(spec.status.to_string(), "200");
+
+        let spec: UnixSpec = parse_inline("/run/app.sock;http").unwrap();
+        assert_eq!(spec.http.as_deref(), Some("/"));
+
+        assert!(parse_inline::<UnixSpec>("/run/app.sock;http=_ping").is_err());
+    }
+
+    #[test]
+    fn plain_target_has_no_settings() {
+        let spec: TcpSpec = parse_inline("db:5432").unwrap();
+
+        assert_eq!(spec.addr, "db:5432");
+        assert!(spec.settings.timeout.is_none());
+        assert!(spec.settings.interval.is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_inline_options() {
+        let entry: toml::Value = toml::from_str("addr = \"db:5432\"\nheader = \"X: 1\"\n").unwrap();
+        let err = entry.try_into::<Entry<TcpSpec>>().unwrap_err();
+        assert!(err.to_string().contains("unknown option header"), "{err}");
+
+        let err = parse_inline::<TcpSpec>("db:5432;interval=fast").unwrap_err();
+        assert!(err.to_string().contains("db:5432"), "{err}");
+
+        assert!(parse_inline::<FileSpec>(";interval=1s").is_err());
+        assert!(parse_inline::<FileSpec>("/tmp/x;threshold=-1").is_err());
+    }
+
+    #[test]
+    fn file_accepts_content_expectations() {
+        let spec: FileSpec = parse_inline("/tmp/hb;max-age=30s;non-empty;matches=^ok").unwrap();
+
+        assert_eq!(spec.expect.max_age, Some(Duration::from_secs(30)));
+        assert!(spec.expect.non_empty);
+        assert!(spec.expect.matches.unwrap().is_match("ok 12"));
+        assert!(parse_inline::<FileSpec>("/tmp/hb;non-empty=yes").is_err());
+        assert!(parse_inline::<FileSpec>("/tmp/hb;max-age=soon").is_err());
+
+        let spec: FileSpec = parse_inline("/run/app.sock;type=socket").unwrap();
+        assert_eq!(spec.expect.kind, Some(FileKind::Socket));
+        let spec: FileSpec = parse_inline("/tmp/migrate.lock;absent").unwrap();
+        assert!(spec.expect.absent);
+        assert!(parse_inline::<FileSpec>("/tmp/x;type=fifo").is_err());
+    }
+
+    #[test]
+    fn file_flags_accept_explicit_booleans() {
+        let spec: FileSpec = parse_inline("/tmp/migrate.lock;absent=true").unwrap();
+        assert!(spec.expect.absent);
+
+        let spec: FileSpec = parse_inline("/tmp/hb;non-empty=true").unwrap();
+        assert!(spec.expect.non_empty);
+        let spec: FileSpec = parse_inline("/tmp/hb;non-empty;non-empty=false").unwrap();
+        assert!(!spec.expect.non_empty);
+
+        let entry: toml::Value = toml::from_str("path = \"/tmp/hb\"\nnon-empty = false\n").unwrap();
+        let Entry(spec) = entry.try_into::<Entry<FileSpec>>().unwrap();
+        assert!(!spec.expect.non_empty);
+    }
+
+    #[test]
+    fn targets_keep_semicolons_that_are_not_options() {
+        let spec: HttpSpec = parse_inline("http://x/health;v=1;timeout=2s;status=204").unwrap();
+        assert_eq!(spec.url, "http://x/health;v=1");
+        assert_eq!(spec.settings.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(spec.expect.status.to_string(), "204");
+
+        let spec: FileSpec = parse_inline("/tmp/a;b;non-empty").unwrap();
+        assert_eq!(spec.path, "/tmp/a;b");
+        assert!(spec.expect.non_empty);
+
+        let spec: TcpSpec = parse_inline("db:5432;").unwrap();
+        assert_eq!(spec.addr, "db:5432");
+    }
+
+    #[test]
+    fn exec_keeps_semicolons_in_command() {
+        let spec: ExecSpec = parse_inline("pg_isready -h db; test -f /ready;timeout=2s").unwrap();
+
+        assert_eq!(spec.command, "pg_isready -h db; test -f /ready");
+        assert_eq!(spec.settings.timeout, Some(Duration::from_secs(2)));
+    }
+}
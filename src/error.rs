@@ -10,6 +10,24 @@ pub enum Error {
     #[error("invalid exit code: {0}")]
     InvalidExitCode(String),
 
+    #[error("invalid config: {0}")]
+    Config(String),
+
+    #[error("invalid check option: {0}")]
+    InvalidCheckOption(String),
+
+    #[error("invalid retry outcome: {0}")]
+    InvalidRetryOn(String),
+
+    #[error("invalid retry backoff: {0}")]
+    InvalidBackoff(String),
+
+    #[error("invalid signal: {0}")]
+    InvalidSignal(String),
+
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(String),
+
     #[error("wait phase timed out")]
     WaitTimeout,
 
@@ -29,4 +47,10 @@ pub enum Error {
     Io(#[from] io::Error),
 }
 
+// Same as coreutils `timeout`: watchctl itself failed, the command could not
+// be run, or the command was not found.
+pub const EXIT_ERROR: u8 = 125;
+pub const EXIT_CANNOT_INVOKE: u8 = 126;
+pub const EXIT_NOT_FOUND: u8 = 127;
+
 pub type Result<T> = std::result::Result<T, Error>;
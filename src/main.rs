@@ -3,17 +3,24 @@ mod cli;
 mod config;
 mod duration;
 mod error;
+mod output;
 mod process;
+#[cfg(unix)]
+mod reaper;
 mod retry;
+mod signal;
 mod wait;
 mod watch;
 
-use config::Config;
-use error::Result;
+use config::{Config, FailureCodes};
+use error::{EXIT_CANNOT_INVOKE, EXIT_ERROR, EXIT_NOT_FOUND, Error, Result};
 use process::Process;
 use retry::RetryState;
+use signal::SignalForwarder;
 use std::fs::File;
 use std::process::ExitCode;
+use tokio::select;
+use tokio::time::Instant;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt::writer::BoxMakeWriter;
@@ -30,7 +37,7 @@ async fn main() -> ExitCode {
         Ok(code) => code,
         Err(e) => {
             error!("{e}");
-            ExitCode::FAILURE
+            exit_code_from_error(&e)
         }
     }
 }
@@ -55,41 +62,173 @@ fn init_logging(log_file: Option<&str>) {
 
 async fn run(args: cli::Args) -> Result<ExitCode> {
     let config = Config::from_args(args)?;
+    let mut signals = SignalForwarder::install()?;
 
+    #[cfg(unix)]
+    if config.process.init || std::process::id() == 1 {
+        reaper::install()?;
+    }
+    #[cfg(not(unix))]
+    if config.process.init {
+        tracing::warn!("init mode is not supported on this platform");
+    }
+
+    supervise(&config, &mut signals).await
+}
+
+async fn supervise(config: &Config, signals: &mut SignalForwarder) -> Result<ExitCode> {
     let mut retry_state = RetryState::new(&config.retry);
     let mut run_wait = true;
 
     loop {
-        if run_wait && let Err(e) = wait::run_wait_phase(&config.wait).await {
-            error!("wait phase failed: {e}");
-            return Err(e);
+        if run_wait {
+            select! {
+                result = wait::run_wait_phase(&config.wait) => {
+                    match result {
+                        Ok(()) => {}
+                        Err(Error::WaitTimeout) => {
+                            error!("wait phase failed: {}", Error::WaitTimeout);
+                            return Ok(ExitCode::from(config.failure_codes.wait_timeout));
+                        }
+                        Err(e) => {
+                            error!("wait phase failed: {e}");
+                            return Err(e);
+                        }
+                    }
+                }
+                signal = signals.recv_termination() => {
+                    info!("received {signal} during wait phase, exiting");
+                    return Ok(exit_code_from_signal(signal));
+                }
+            }
         }
 
         info!("starting command: {:?}", config.command);
-        let process = Process::spawn(&config.command)?;
+        let started = Instant::now();
+        let process = Process::spawn(&config.command, &config.process)?;
+
+        let result = watch::run_watch_phase(&config.watch, process, signals).await?;
+        retry_state.attempt_finished(&config.retry, started.elapsed());
 
-        let result = watch::run_watch_phase(&config.watch, process).await?;
+        let codes = &config.failure_codes;
+        if let Some(signal) = signals.terminating() {
+            info!("not retrying after {signal}");
+            return Ok(exit_code_from_result(&result, codes));
+        }
 
-        let exit_status = match result {
-            WatchResult::ProcessExited(status) => Some(status),
-            WatchResult::HealthCheckFailed(_) | WatchResult::Timeout => {
-                return Ok(ExitCode::FAILURE);
+        if !retry_state.should_retry(&config.retry, &result) {
+            if retry_state.exhausted(&config.retry, &result) {
+                error!("no retries left, giving up");
+                return Ok(ExitCode::from(codes.retries_exhausted));
             }
-        };
+            return Ok(exit_code_from_result(&result, codes));
+        }
 
-        if !retry_state.should_retry(&config.retry, exit_status) {
-            return Ok(exit_code_from_status(exit_status.unwrap()));
+        let delay = retry_state.next_delay(&config.retry);
+        if !retry_state.restart_allowed(&config.retry, Instant::now() + delay) {
+            if let Some(limit) = config.retry.limit {
+                error!(
+                    "restarted {} times within {:?}, giving up",
+                    limit.burst, limit.interval
+                );
+            }
+            return Ok(ExitCode::from(codes.restart_limit));
         }
 
-        retry_state.wait_before_retry(&config.retry).await;
+        select! {
+            _ = retry_state.wait_before_retry(&config.retry, delay) => {}
+            signal = signals.recv_termination() => {
+                info!("received {signal} before retry, exiting");
+                return Ok(exit_code_from_result(&result, codes));
+            }
+        }
         run_wait = config.retry.with_wait;
     }
 }
 
+fn exit_code_from_result(result: &WatchResult, codes: &FailureCodes) -> ExitCode {
+    match result {
+        WatchResult::ProcessExited(status) => exit_code_from_status(*status),
+        WatchResult::HealthCheckFailed(_) => ExitCode::from(codes.health),
+        WatchResult::Timeout => ExitCode::from(codes.watch_timeout),
+        WatchResult::OutputIdle => ExitCode::from(codes.idle),
+    }
+}
+
 fn exit_code_from_status(status: std::process::ExitStatus) -> ExitCode {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return ExitCode::from((128 + signal).clamp(1, 255) as u8);
+    }
     match status.code() {
         Some(0) => ExitCode::SUCCESS,
         Some(code) => ExitCode::from(code.clamp(1, 255) as u8),
         None => ExitCode::FAILURE,
     }
 }
+
+fn exit_code_from_error(e: &Error) -> ExitCode {
+    match e {
+        Error::ProcessSpawn(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            ExitCode::from(EXIT_NOT_FOUND)
+        }
+        Error::ProcessSpawn(_) => ExitCode::from(EXIT_CANNOT_INVOKE),
+        _ => ExitCode::from(EXIT_ERROR),
+    }
+}
+
+fn exit_code_from_signal(signal: signal::Signal) -> ExitCode {
+    ExitCode::from((128 + signal.number()).clamp(1, 255) as u8)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use signal::parse_signal;
+    use std::time::Duration;
+
+    fn config(args: &[&str]) -> Config {
+        let args = cli::Args::try_parse_from(["watchctl"].iter().chain(args))
+            .expect("arguments should parse");
+        Config::from_args(args).expect("config should load")
+    }
+
+    async fn terminate_after(config: &Config, delay: Duration) -> ExitCode {
+        let (tx, mut signals) = SignalForwarder::channel();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = tx.send(parse_signal("TERM").unwrap());
+        });
+        tokio::time::timeout(Duration::from_secs(10), supervise(config, &mut signals))
+            .await
+            .expect("watchctl should exit after SIGTERM")
+            .expect("supervise should succeed")
+    }
+
+    #[tokio::test]
+    async fn forwarded_termination_stops_command_without_retrying() {
+        let config = config(&["--retry-times", "0", "--", "sleep", "30"]);
+
+        let code = terminate_after(&config, Duration::from_millis(200)).await;
+
+        assert_eq!(code, ExitCode::from(143));
+    }
+
+    #[tokio::test]
+    async fn command_ignoring_forwarded_termination_is_killed_after_stop_timeout() {
+        let config = config(&[
+            "--stop-timeout",
+            "200ms",
+            "--process-group",
+            "--",
+            "sh",
+            "-c",
+            "trap '' TERM; sleep 30",
+        ]);
+
+        let code = terminate_after(&config, Duration::from_millis(200)).await;
+
+        assert_eq!(code, ExitCode::from(137));
+    }
+}
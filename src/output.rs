@@ -0,0 +1,237 @@
+use regex::Regex;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::{ChildStderr, ChildStdout};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, sleep_until, timeout};
+use tracing::{debug, info};
+
+const MAX_LINE: usize = 64 * 1024;
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default)]
+pub struct Patterns {
+    pub ready: Option<Regex>,
+    pub fail: Option<Regex>,
+}
+
+// Copies the child's stdout and stderr to our own while matching complete
+// lines against the configured patterns.
+pub struct Tee {
+    matcher: Arc<Matcher>,
+    tasks: Vec<JoinHandle<()>>,
+    ready: Option<oneshot::Receiver<String>>,
+    failed: Option<oneshot::Receiver<String>>,
+}
+
+struct Matcher {
+    ready: Trigger,
+    fail: Trigger,
+    last_output: Mutex<Instant>,
+}
+
+// Reports the first line matching a pattern.
+struct Trigger {
+    pattern: Option<Regex>,
+    tx: Mutex<Option<oneshot::Sender<String>>>,
+}
+
+impl Tee {
+    pub fn spawn(stdout: ChildStdout, stderr: ChildStderr, patterns: Patterns) -> Self {
+        let (ready, ready_rx) = Trigger::new(patterns.ready);
+        let (fail, failed_rx) = Trigger::new(patterns.fail);
+        let matcher = Arc::new(Matcher::new(ready, fail));
+
+        let tasks = vec![
+            tokio::spawn(pump(stdout, tokio::io::stdout(), Arc::clone(&matcher))),
+            tokio::spawn(pump(stderr, tokio::io::stderr(), Arc::clone(&matcher))),
+        ];
+
+        Self {
+            matcher,
+            tasks,
+            ready: Some(ready_rx),
+            failed: Some(failed_rx),
+        }
+    }
+
+    // Resolves with the matching line once the ready pattern is seen; never
+    // resolves when there is no pattern or the output ends without a match.
+    pub fn ready(&mut self) -> impl Future<Output = String> + use<> {
+        first_match(self.ready.take())
+    }
+
+    // Resolves with the first line matching the fail pattern.
+    pub fn failed(&mut self) -> impl Future<Output = String> + use<> {
+        first_match(self.failed.take())
+    }
+
+    // Resolves once neither stream has produced a byte for `limit`.
+    pub fn idle(&self, limit: Duration) -> impl Future<Output = ()> + use<> {
+        let matcher = Arc::clone(&self.matcher);
+        async move {
+            loop {
+                let deadline = matcher.last_output() + limit;
+                if Instant::now() >= deadline {
+                    return;
+                }
+                sleep_until(deadline).await;
+            }
+        }
+    }
+
+    // Waits briefly for output still in the pipes after the child exits. A
+    // leftover grandchild may hold them open, so this does not wait for EOF
+    // indefinitely.
+    pub async fn finish(self) {
+        let pending = futures::future::join_all(self.tasks);
+        if timeout(FLUSH_TIMEOUT, pending).await.is_err() {
+            debug!("output still open after process exit, no longer waiting for it");
+        }
+    }
+}
+
+async fn first_match(rx: Option<oneshot::Receiver<String>>) -> String {
+    if let Some(rx) = rx
+        && let Ok(line) = rx.await
+    {
+        return line;
+    }
+    std::future::pending().await
+}
+
+impl Matcher {
+    fn new(ready: Trigger, fail: Trigger) -> Self {
+        Self {
+            ready,
+            fail,
+            last_output: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn output(&self) {
+        *self.last_output.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn last_output(&self) -> Instant {
+        *self.last_output.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn line(&self, raw: &[u8]) {
+        let text = String::from_utf8_lossy(raw);
+        let line = text.trim_end_matches(['\n', '\r']);
+
+        if self.ready.matched(line) {
+            info!("ready pattern matched: {line}");
+        }
+        if self.fail.matched(line) {
+            info!("fail pattern matched: {line}");
+        }
+    }
+}
+
+impl Trigger {
+    fn new(pattern: Option<Regex>) -> (Self, oneshot::Receiver<String>) {
+        let (tx, rx) = oneshot::channel();
+        let tx = Mutex::new(pattern.is_some().then_some(tx));
+        (Self { pattern, tx }, rx)
+    }
+
+    fn matched(&self, line: &str) -> bool {
+        let Some(pattern) = &self.pattern else {
+            return false;
+        };
+        if !pattern.is_match(line) {
+            return false;
+        }
+        let mut tx = self.tx.lock().unwrap_or_else(|e| e.into_inner());
+        match tx.take() {
+            Some(tx) => tx.send(line.to_string()).is_ok(),
+            None => false,
+        }
+    }
+}
+
+async fn pump(
+    mut reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+    matcher: Arc<Matcher>,
+) {
+    let mut buf = [0; 8192];
+    let mut line = Vec::new();
+
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        matcher.output();
+        let _ = writer.write_all(&buf[..n]).await;
+        let _ = writer.flush().await;
+
+        line.extend_from_slice(&buf[..n]);
+        while let Some(end) = line.iter().position(|b| *b == b'\n') {
+            let rest = line.split_off(end + 1);
+            matcher.line(&line);
+            line = rest;
+        }
+        if line.len() > MAX_LINE {
+            matcher.line(&line);
+            line.clear();
+        }
+    }
+
+    if !line.is_empty() {
+        matcher.line(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(ready: &str) -> (Arc<Matcher>, oneshot::Receiver<String>) {
+        let (ready, rx) = Trigger::new(Some(Regex::new(ready).unwrap()));
+        let (fail, _) = Trigger::new(None);
+        (Arc::new(Matcher::new(ready, fail)), rx)
+    }
+
+    #[tokio::test]
+    async fn passes_output_through_and_matches_ready_line() {
+        let (matcher, ready) = matcher(r"Server started on \d+");
+        let input: &[u8] = b"loading config\r\nServer started on 8080\nserving\n";
+        let mut output = Vec::new();
+
+        pump(input, &mut output, matcher).await;
+
+        assert_eq!(output, input);
+        assert_eq!(ready.await.unwrap(), "Server started on 8080");
+    }
+
+    #[tokio::test]
+    async fn matches_last_line_without_newline() {
+        let (matcher, ready) = matcher("^ready$");
+
+        pump(&b"booting\nready"[..], Vec::new(), matcher).await;
+
+        assert_eq!(ready.await.unwrap(), "ready");
+    }
+
+    #[tokio::test]
+    async fn reports_only_first_fail_line() {
+        let (ready, _) = Trigger::new(None);
+        let (fail, failed) = Trigger::new(Some(Regex::new("OutOfMemoryError|FATAL").unwrap()));
+        let matcher = Arc::new(Matcher::new(ready, fail));
+        let input: &[u8] =
+            b"working\njava.lang.OutOfMemoryError: Java heap space\nFATAL: deadlock detected\n";
+
+        pump(input, Vec::new(), matcher).await;
+
+        assert_eq!(
+            failed.await.unwrap(),
+            "java.lang.OutOfMemoryError: Java heap space"
+        );
+    }
+}
@@ -1,14 +1,34 @@
+use crate::config::{ProcessConfig, ProcessGroup};
 use crate::error::{Error, Result};
+#[cfg(unix)]
+use crate::reaper;
+use crate::signal::Signal;
 use std::process::{ExitStatus, Stdio};
-use tokio::process::{Child, Command};
-use tracing::debug;
+use std::time::Duration;
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+#[cfg(unix)]
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+enum Waiter {
+    Child,
+    #[cfg(unix)]
+    Reaper(oneshot::Receiver<ExitStatus>),
+}
 
 pub struct Process {
     child: Child,
+    waiter: Waiter,
+    status: Option<ExitStatus>,
+    pid: Option<u32>,
+    group: bool,
+    stop_signal: Signal,
+    stop_timeout: Duration,
 }
 
 impl Process {
-    pub fn spawn(command: &[String]) -> Result<Self> {
+    pub fn spawn(command: &[String], config: &ProcessConfig) -> Result<Self> {
         let (program, args) = command.split_first().ok_or_else(|| {
             Error::ProcessSpawn(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
@@ -18,29 +38,256 @@ impl Process {
 
         debug!("spawning process: {program} {:?}", args);
 
-        let child = Command::new(program)
+        let mut command = Command::new(program);
+        let output = if config.capture_output {
+            Stdio::piped
+        } else {
+            Stdio::inherit
+        };
+        command
             .args(args)
             .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
-            .map_err(Error::ProcessSpawn)?;
+            .stdout(output())
+            .stderr(output());
+        set_process_group(&mut command, config.group);
 
-        Ok(Self { child })
+        let (child, waiter) = spawn_child(&mut command).map_err(Error::ProcessSpawn)?;
+
+        Ok(Self {
+            pid: child.id(),
+            child,
+            waiter,
+            status: None,
+            group: config.group != ProcessGroup::Inherit,
+            stop_signal: config.stop_signal,
+            stop_timeout: config.stop_timeout,
+        })
     }
 
     pub async fn wait(&mut self) -> Result<ExitStatus> {
-        self.child.wait().await.map_err(Error::Io)
+        if let Some(status) = self.status {
+            return Ok(status);
+        }
+
+        let status = match &mut self.waiter {
+            Waiter::Child => self.child.wait().await?,
+            #[cfg(unix)]
+            Waiter::Reaper(rx) => rx
+                .await
+                .map_err(|_| Error::Io(std::io::Error::other("process status was lost")))?,
+        };
+        self.status = Some(status);
+        Ok(status)
+    }
+
+    fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        if self.status.is_none() {
+            self.status = match &mut self.waiter {
+                Waiter::Child => self.child.try_wait()?,
+                #[cfg(unix)]
+                Waiter::Reaper(rx) => rx.try_recv().ok(),
+            };
+        }
+        Ok(self.status)
+    }
+
+    pub async fn stop(&mut self) -> Result<ExitStatus> {
+        if let Some(status) = self.try_wait()? {
+            return Ok(status);
+        }
+
+        if self.stop_signal != Signal::KILL {
+            self.signal(self.stop_signal)?;
+
+            match timeout(self.stop_timeout, self.wait()).await {
+                Ok(status) => {
+                    let status = status?;
+                    info!("process exited after {}", self.stop_signal);
+                    return Ok(status);
+                }
+                Err(_) => warn!(
+                    "process did not exit within {:?} after {}, sending {}",
+                    self.stop_timeout,
+                    self.stop_signal,
+                    Signal::KILL
+                ),
+            }
+        }
+
+        self.signal(Signal::KILL)?;
+        let status = self.wait().await?;
+        info!("process killed with {}", Signal::KILL);
+        Ok(status)
     }
 
-    pub async fn kill_and_wait(&mut self) -> Result<()> {
-        self.child.kill().await.map_err(Error::Io)?;
-        self.child.wait().await.map_err(Error::Io)?;
+    #[cfg(unix)]
+    pub fn signal(&mut self, signal: Signal) -> Result<()> {
+        if self.try_wait()?.is_some() {
+            return Ok(());
+        }
+        let Some(pid) = self.child.id() else {
+            return Ok(());
+        };
+
+        let target = if self.group {
+            debug!("sending {signal} to process group {pid}");
+            -(pid as libc::pid_t)
+        } else {
+            debug!("sending {signal} to process {pid}");
+            pid as libc::pid_t
+        };
+
+        if unsafe { libc::kill(target, signal.number()) } == -1 {
+            return Err(Error::Io(std::io::Error::last_os_error()));
+        }
         Ok(())
     }
 
+    #[cfg(not(unix))]
+    pub fn signal(&mut self, signal: Signal) -> Result<()> {
+        debug!("{signal} is not supported on this platform, killing process");
+        self.child.start_kill().map_err(Error::Io)
+    }
+
+    pub fn stop_timeout(&self) -> Duration {
+        self.stop_timeout
+    }
+
+    pub fn take_output(&mut self) -> Option<(ChildStdout, ChildStderr)> {
+        Some((self.child.stdout.take()?, self.child.stderr.take()?))
+    }
+
     #[allow(dead_code)]
     pub fn id(&self) -> Option<u32> {
         self.child.id()
     }
 }
+
+#[cfg(unix)]
+impl Drop for Process {
+    fn drop(&mut self) {
+        if !self.group {
+            return;
+        }
+        let Some(pid) = self.pid else {
+            return;
+        };
+
+        if unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) } == 0 {
+            info!("killed remaining processes in group {pid}");
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_process_group(command: &mut Command, group: ProcessGroup) {
+    match group {
+        ProcessGroup::Inherit => {}
+        ProcessGroup::Group => {
+            command.process_group(0);
+        }
+        ProcessGroup::Session => unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        },
+    }
+}
+
+#[cfg(not(unix))]
+fn set_process_group(_command: &mut Command, group: ProcessGroup) {
+    if group != ProcessGroup::Inherit {
+        warn!("process groups are not supported on this platform");
+    }
+}
+
+#[cfg(unix)]
+fn spawn_child(command: &mut Command) -> std::io::Result<(Child, Waiter)> {
+    if reaper::is_installed() {
+        let (child, rx) = reaper::spawn(command)?;
+        return Ok((child, Waiter::Reaper(rx)));
+    }
+    Ok((command.spawn()?, Waiter::Child))
+}
+
+#[cfg(not(unix))]
+fn spawn_child(command: &mut Command) -> std::io::Result<(Child, Waiter)> {
+    Ok((command.spawn()?, Waiter::Child))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::signal::parse_signal;
+    use std::os::unix::process::ExitStatusExt;
+
+    fn spawn_shell(script: &str, stop_timeout: Duration) -> Process {
+        spawn_shell_in(script, stop_timeout, ProcessGroup::Inherit)
+    }
+
+    fn spawn_shell_in(script: &str, stop_timeout: Duration, group: ProcessGroup) -> Process {
+        let config = ProcessConfig {
+            stop_signal: parse_signal("SIGTERM").unwrap(),
+            stop_timeout,
+            init: false,
+            group,
+            capture_output: false,
+        };
+        let command = ["sh", "-c", script].map(String::from);
+        Process::spawn(&command, &config).expect("shell should spawn")
+    }
+
+    #[tokio::test]
+    async fn stop_sends_stop_signal_first() {
+        let mut process = spawn_shell("sleep 30", Duration::from_secs(5));
+
+        let status = process.stop().await.expect("stop should succeed");
+
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+
+    #[tokio::test]
+    async fn stop_escalates_to_kill_after_grace_period() {
+        let mut process = spawn_shell("trap '' TERM; sleep 30", Duration::from_millis(200));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let status = process.stop().await.expect("stop should succeed");
+
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropping_grouped_process_kills_leftover_children() {
+        let dir = std::env::temp_dir().join(format!("watchctl-group-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+        let script = format!("sleep 30 & echo $! > {}", pid_file.display());
+
+        let mut process = spawn_shell_in(&script, Duration::from_secs(1), ProcessGroup::Group);
+        process.wait().await.expect("shell should exit");
+        let pid = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .to_string();
+        drop(process);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap_or_default();
+        let state = stat
+            .rsplit(')')
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .next();
+        assert!(
+            matches!(state, None | Some("Z")),
+            "leftover process state: {state:?}"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::Result;
+use std::collections::HashMap;
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::{Mutex, OnceLock};
+use tokio::process::{Child, Command};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+static WAITERS: OnceLock<Mutex<HashMap<u32, oneshot::Sender<ExitStatus>>>> = OnceLock::new();
+
+pub fn install() -> Result<()> {
+    if WAITERS.get().is_some() {
+        return Ok(());
+    }
+
+    let mut sigchld = signal(SignalKind::child())?;
+    WAITERS.get_or_init(Default::default);
+
+    #[cfg(target_os = "linux")]
+    if std::process::id() != 1 && unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1) } == -1 {
+        warn!(
+            "failed to become child subreaper: {}",
+            io::Error::last_os_error()
+        );
+    }
+
+    info!("init mode enabled, reaping orphaned processes");
+
+    tokio::spawn(async move {
+        loop {
+            reap();
+            if sigchld.recv().await.is_none() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+pub fn is_installed() -> bool {
+    WAITERS.get().is_some()
+}
+
+// The waiter is registered while the lock is held so the reaper cannot
+// collect the child before anyone is listening for its status.
+pub fn spawn(command: &mut Command) -> io::Result<(Child, oneshot::Receiver<ExitStatus>)> {
+    let waiters = WAITERS
+        .get()
+        .ok_or_else(|| io::Error::other("reaper is not installed"))?;
+    let mut waiters = waiters.lock().unwrap_or_else(|e| e.into_inner());
+
+    let child = command.spawn()?;
+    let (tx, rx) = oneshot::channel();
+    if let Some(pid) = child.id() {
+        waiters.insert(pid, tx);
+    }
+
+    Ok((child, rx))
+}
+
+// Kills a child started with `spawn` only while its status is still pending;
+// once reaped, its pid may already belong to another process.
+pub fn kill(child: &Child) -> io::Result<()> {
+    let (Some(waiters), Some(pid)) = (WAITERS.get(), child.id()) else {
+        return Ok(());
+    };
+    let waiters = waiters.lock().unwrap_or_else(|e| e.into_inner());
+    if !waiters.contains_key(&pid) {
+        return Ok(());
+    }
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn reap() {
+    let Some(waiters) = WAITERS.get() else {
+        return;
+    };
+    let mut waiters = waiters.lock().unwrap_or_else(|e| e.into_inner());
+
+    loop {
+        let mut status = 0;
+        let pid = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+        if pid <= 0 {
+            break;
+        }
+
+        match waiters.remove(&(pid as u32)) {
+            Some(tx) => {
+                let _ = tx.send(ExitStatus::from_raw(status));
+            }
+            None => debug!("reaped orphaned process {pid}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::{Check, ExecCheck};
+    use std::process::Stdio;
+    use std::time::Duration;
+
+    const ISOLATED: &str = "WATCHCTL_REAPER_TEST";
+
+    // The reaper waits on every child of the process, so it runs in a copy of
+    // the test binary that runs nothing else.
+    #[test]
+    fn reaper_reports_child_status_and_collects_orphans() {
+        let name = "reaper::tests::reaper_reports_child_status_and_collects_orphans";
+        if std::env::var_os(ISOLATED).is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", name, "--nocapture"])
+                .env(ISOLATED, "1")
+                .output()
+                .expect("test binary should run");
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(
+                output.status.success() && stdout.contains("1 passed"),
+                "{stdout}{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return;
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            install().expect("reaper should install");
+
+            let (_child, status) = spawn(Command::new("sh").args(["-c", "exit 3"])).unwrap();
+            let status = tokio::time::timeout(Duration::from_secs(5), status)
+                .await
+                .expect("status should arrive")
+                .expect("status should be sent");
+            assert_eq!(status.code(), Some(3));
+
+            let (mut child, status) = spawn(
+                Command::new("sh")
+                    .args(["-c", "sleep 0.2 & echo $!"])
+                    .stdout(Stdio::piped()),
+            )
+            .unwrap();
+            let mut pid = String::new();
+            tokio::io::AsyncReadExt::read_to_string(child.stdout.as_mut().unwrap(), &mut pid)
+                .await
+                .unwrap();
+            assert_eq!(status.await.unwrap().code(), Some(0));
+
+            #[cfg(target_os = "linux")]
+            {
+                let stat = format!("/proc/{}/stat", pid.trim());
+                tokio::time::sleep(Duration::from_millis(600)).await;
+                assert!(
+                    !std::path::Path::new(&stat).exists(),
+                    "orphan {} was not reaped",
+                    pid.trim()
+                );
+            }
+
+            let check = ExecCheck::new("sleep 30".to_string(), Duration::from_millis(100));
+            let err = check.check().await.unwrap_err();
+            assert!(err.contains("timed out"), "{err}");
+            assert_eq!(
+                ExecCheck::new("exit 0".to_string(), Duration::from_secs(5))
+                    .check()
+                    .await,
+                Ok(())
+            );
+        });
+    }
+}
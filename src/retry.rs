@@ -1,72 +1,189 @@
-use crate::config::{RetryCondition, RetryConfig};
-use std::process::ExitStatus;
-use std::time::Duration;
-use tokio::time::sleep;
+use crate::config::{Backoff, Jitter, RetryCondition, RetryConfig, RetryOn};
+use crate::watch::WatchResult;
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::{Instant, sleep};
 use tracing::info;
 
-const MAX_BACKOFF_DELAY: Duration = Duration::from_secs(300);
-
 pub struct RetryState {
     // None = infinite, Some(0) = exhausted, Some(N) = N retries remaining
     pub attempts_remaining: Option<u32>,
     pub current_delay: Duration,
+    restarts: VecDeque<Instant>,
+    rng: Rng,
 }
 
 impl RetryState {
     pub fn new(config: &RetryConfig) -> Self {
-        let attempts_remaining = match config.times {
-            None => Some(0), // not specified: no retries
-            Some(0) => None, // 0 means infinite
-            Some(n) => Some(n),
-        };
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+            ^ (u64::from(std::process::id()) << 32);
+        Self::with_seed(config, seed)
+    }
+
+    pub fn with_seed(config: &RetryConfig, seed: u64) -> Self {
         Self {
-            attempts_remaining,
+            attempts_remaining: budget(config),
             current_delay: config.delay,
+            restarts: VecDeque::new(),
+            rng: Rng(seed),
         }
     }
 
-    pub fn should_retry(&self, config: &RetryConfig, exit_status: Option<ExitStatus>) -> bool {
-        if self.attempts_remaining == Some(0) {
-            return false;
+    // An attempt that stayed up for `reset_after` counts as a recovery, so
+    // later failures start again from the full budget and base delay.
+    pub fn attempt_finished(&mut self, config: &RetryConfig, uptime: Duration) {
+        if config
+            .reset_after
+            .is_some_and(|reset_after| uptime >= reset_after)
+        {
+            if self.attempts_remaining != budget(config) {
+                info!("command ran for {uptime:?}, resetting retry budget");
+            }
+            self.attempts_remaining = budget(config);
+            self.current_delay = config.delay;
         }
+    }
 
-        let code = exit_status.and_then(|s| s.code());
+    pub fn should_retry(&self, config: &RetryConfig, result: &WatchResult) -> bool {
+        self.attempts_remaining != Some(0) && retryable(config, result)
+    }
 
-        match (&config.condition, code) {
-            (RetryCondition::AnyNonZero, Some(0)) => false,
-            (RetryCondition::AnyNonZero, _) => true,
-            (RetryCondition::Only(codes), Some(c)) => codes.contains(&c),
-            (RetryCondition::Only(_), None) => true,
-            (RetryCondition::Except(_), Some(0)) => false,
-            (RetryCondition::Except(codes), Some(c)) => !codes.contains(&c),
-            (RetryCondition::Except(_), None) => true,
+    // Retries were configured and have run out on an outcome that would
+    // otherwise have been retried.
+    pub fn exhausted(&self, config: &RetryConfig, result: &WatchResult) -> bool {
+        config.times.is_some() && self.attempts_remaining == Some(0) && retryable(config, result)
+    }
+
+    // Whether a restart at `at`, when its retry delay ends, stays within the
+    // configured burst for the sliding window.
+    pub fn restart_allowed(&mut self, config: &RetryConfig, at: Instant) -> bool {
+        let Some(limit) = config.limit else {
+            return true;
+        };
+        while let Some(first) = self.restarts.front()
+            && at.duration_since(*first) >= limit.interval
+        {
+            self.restarts.pop_front();
         }
+        self.restarts.len() < limit.burst as usize
     }
 
-    pub async fn wait_before_retry(&mut self, config: &RetryConfig) {
+    // Only counts toward the restart limit once the delay is over and the
+    // command is about to be started again.
+    pub async fn wait_before_retry(&mut self, config: &RetryConfig, delay: Duration) {
         let remaining_label = self
             .attempts_remaining
             .map_or("infinite".to_string(), |n| n.to_string());
-        info!(
-            "retrying in {:?} ({} attempts remaining)",
-            self.current_delay, remaining_label
-        );
-        sleep(self.current_delay).await;
+        info!("retrying in {delay:?} ({remaining_label} attempts remaining)");
+        sleep(delay).await;
+        self.restarted(config, Instant::now());
 
         if let Some(ref mut n) = self.attempts_remaining {
             *n -= 1;
         }
+    }
+
+    fn restarted(&mut self, config: &RetryConfig, at: Instant) {
+        if config.limit.is_some() {
+            self.restarts.push_back(at);
+        }
+    }
+
+    // Returns the delay before this retry and advances to the next one.
+    // Only growth is capped by `max_delay`; a longer base delay is kept as is.
+    pub fn next_delay(&mut self, config: &RetryConfig) -> Duration {
+        let base = config.delay;
+        let cap = config.max_delay.max(base);
+        let current = self.current_delay;
+
+        if config.backoff == Backoff::Decorrelated {
+            // Each delay is drawn between the base and three times the last
+            // one, which already spreads out restarts without extra jitter.
+            let upper = current.saturating_mul(3).min(cap);
+            let delay = self.rng.between(base, upper);
+            self.current_delay = delay;
+            return delay;
+        }
+
+        self.current_delay = match config.backoff {
+            Backoff::Constant | Backoff::Decorrelated => current,
+            Backoff::Linear => current.saturating_add(config.delay),
+            Backoff::Exponential(factor) => {
+                Duration::try_from_secs_f64(current.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+            }
+        }
+        .min(cap);
+
+        match config.jitter {
+            Jitter::None => current,
+            Jitter::Full => self.rng.between(Duration::ZERO, current),
+            Jitter::Equal => self.rng.between(current / 2, current),
+        }
+    }
+}
 
-        if config.backoff {
-            self.current_delay = self.current_delay.saturating_mul(2).min(MAX_BACKOFF_DELAY);
+fn retryable(config: &RetryConfig, result: &WatchResult) -> bool {
+    let status = match result {
+        WatchResult::ProcessExited(status) => status,
+        WatchResult::HealthCheckFailed(_) => return config.on.contains(&RetryOn::Health),
+        WatchResult::Timeout => return config.on.contains(&RetryOn::Timeout),
+        WatchResult::OutputIdle => return config.on.contains(&RetryOn::Idle),
+    };
+
+    if !config.on.contains(&RetryOn::Exit) {
+        return false;
+    }
+
+    let code = status.code();
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(status);
+    #[cfg(not(unix))]
+    let signal = None;
+
+    match &config.condition {
+        RetryCondition::AnyNonZero => code != Some(0),
+        RetryCondition::Only(codes) => codes.matches(code, signal),
+        RetryCondition::Except(codes) => code != Some(0) && !codes.matches(code, signal),
+    }
+}
+
+fn budget(config: &RetryConfig) -> Option<u32> {
+    match config.times {
+        None => Some(0), // not specified: no retries
+        Some(0) => None, // 0 means infinite
+        Some(n) => Some(n),
+    }
+}
+
+// SplitMix64: enough to spread retries apart, and reproducible from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn between(&mut self, low: Duration, high: Duration) -> Duration {
+        let span = high.saturating_sub(low).as_nanos() as u64;
+        if span == 0 {
+            return low;
         }
+        low + Duration::from_nanos(self.next() % (span + 1))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{ExitCodes, RestartLimit};
     use std::collections::HashSet;
+    use std::process::ExitStatus;
 
     #[cfg(unix)]
     use std::os::unix::process::ExitStatusExt;
@@ -75,12 +192,28 @@ mod tests {
         RetryConfig {
             times: Some(3),
             delay: Duration::from_secs(1),
-            backoff: false,
+            backoff: Backoff::Constant,
+            max_delay: Duration::from_secs(300),
+            jitter: Jitter::None,
+            reset_after: None,
+            limit: None,
             condition,
+            on: HashSet::from([RetryOn::Exit]),
             with_wait: false,
         }
     }
 
+    fn codes(list: &[i32]) -> ExitCodes {
+        ExitCodes {
+            codes: list.iter().map(|c| *c..=*c).collect(),
+            ..ExitCodes::default()
+        }
+    }
+
+    fn exited(code: i32) -> WatchResult {
+        WatchResult::ProcessExited(status(code))
+    }
+
     fn status(code: i32) -> ExitStatus {
         #[cfg(unix)]
         {
@@ -103,52 +236,236 @@ mod tests {
         let config = retry_config(RetryCondition::AnyNonZero);
         let state = RetryState::new(&config);
 
-        assert!(!state.should_retry(&config, Some(status(0))));
-        assert!(state.should_retry(&config, Some(status(1))));
+        assert!(!state.should_retry(&config, &exited(0)));
+        assert!(state.should_retry(&config, &exited(1)));
     }
 
     #[test]
     fn only_retries_selected_exit_codes() {
-        let config = retry_config(RetryCondition::Only(HashSet::from([1, 3])));
+        let config = retry_config(RetryCondition::Only(codes(&[1, 3])));
         let state = RetryState::new(&config);
 
-        assert!(state.should_retry(&config, Some(status(1))));
-        assert!(!state.should_retry(&config, Some(status(2))));
-        assert!(!state.should_retry(&config, Some(status(0))));
+        assert!(state.should_retry(&config, &exited(1)));
+        assert!(!state.should_retry(&config, &exited(2)));
+        assert!(!state.should_retry(&config, &exited(0)));
     }
 
     #[test]
     fn except_skips_excluded_exit_codes_and_success() {
-        let config = retry_config(RetryCondition::Except(HashSet::from([2, 78])));
+        let config = retry_config(RetryCondition::Except(codes(&[2, 78])));
         let state = RetryState::new(&config);
 
-        assert!(state.should_retry(&config, Some(status(1))));
-        assert!(!state.should_retry(&config, Some(status(2))));
-        assert!(!state.should_retry(&config, Some(status(78))));
-        assert!(!state.should_retry(&config, Some(status(0))));
+        assert!(state.should_retry(&config, &exited(1)));
+        assert!(!state.should_retry(&config, &exited(2)));
+        assert!(!state.should_retry(&config, &exited(78)));
+        assert!(!state.should_retry(&config, &exited(0)));
     }
 
     #[cfg(unix)]
     #[test]
-    fn signaled_processes_follow_non_zero_retry_conditions() {
+    fn signaled_processes_match_signal_conditions() {
         let any_non_zero = retry_config(RetryCondition::AnyNonZero);
-        let only = retry_config(RetryCondition::Only(HashSet::from([1])));
-        let except = retry_config(RetryCondition::Except(HashSet::from([1])));
-        let signaled = Some(signaled_status(9));
+        let only = retry_config(RetryCondition::Only(codes(&[1])));
+        let except = retry_config(RetryCondition::Except(codes(&[1])));
+        let killed = WatchResult::ProcessExited(signaled_status(libc::SIGKILL));
+        let terminated = WatchResult::ProcessExited(signaled_status(libc::SIGTERM));
+
+        assert!(RetryState::new(&any_non_zero).should_retry(&any_non_zero, &killed));
+        assert!(!RetryState::new(&only).should_retry(&only, &killed));
+        assert!(RetryState::new(&except).should_retry(&except, &killed));
+
+        let mut oom = codes(&[1]);
+        oom.signals.push(libc::SIGKILL);
+        let only = retry_config(RetryCondition::Only(oom.clone()));
+        assert!(RetryState::new(&only).should_retry(&only, &killed));
+        assert!(!RetryState::new(&only).should_retry(&only, &terminated));
+
+        let except = retry_config(RetryCondition::Except(ExitCodes {
+            any_signal: true,
+            ..ExitCodes::default()
+        }));
+        assert!(!RetryState::new(&except).should_retry(&except, &terminated));
+        assert!(RetryState::new(&except).should_retry(&except, &exited(1)));
+    }
+
+    #[test]
+    fn watch_failures_retry_only_when_enabled() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        let state = RetryState::new(&config);
+        let health = WatchResult::HealthCheckFailed("down".to_string());
+        let idle = WatchResult::OutputIdle;
+
+        assert!(!state.should_retry(&config, &health));
+        assert!(!state.should_retry(&config, &WatchResult::Timeout));
+        assert!(!state.should_retry(&config, &idle));
 
-        assert!(RetryState::new(&any_non_zero).should_retry(&any_non_zero, signaled));
-        assert!(RetryState::new(&only).should_retry(&only, signaled));
-        assert!(RetryState::new(&except).should_retry(&except, signaled));
+        config.on = HashSet::from([RetryOn::Health, RetryOn::Timeout]);
+        assert!(state.should_retry(&config, &health));
+        assert!(state.should_retry(&config, &WatchResult::Timeout));
+        assert!(!state.should_retry(&config, &idle));
+        assert!(!state.should_retry(&config, &exited(1)));
+
+        config.on = HashSet::from([RetryOn::Idle]);
+        assert!(state.should_retry(&config, &idle));
     }
 
     #[test]
     fn exhausted_retries_override_condition() {
         let config = retry_config(RetryCondition::AnyNonZero);
-        let state = RetryState {
-            attempts_remaining: Some(0),
-            current_delay: Duration::from_secs(1),
+        let mut state = RetryState::new(&config);
+        state.attempts_remaining = Some(0);
+
+        assert!(!state.should_retry(&config, &exited(1)));
+    }
+
+    #[test]
+    fn stable_uptime_restores_budget_and_delay() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.backoff = Backoff::Exponential(2.0);
+        config.reset_after = Some(Duration::from_secs(60));
+        let mut state = RetryState::with_seed(&config, 0);
+        state.attempts_remaining = Some(0);
+        state.current_delay = Duration::from_secs(16);
+
+        state.attempt_finished(&config, Duration::from_secs(59));
+        assert_eq!(state.attempts_remaining, Some(0));
+        assert_eq!(state.current_delay, Duration::from_secs(16));
+
+        state.attempt_finished(&config, Duration::from_secs(60));
+        assert_eq!(state.attempts_remaining, Some(3));
+        assert_eq!(state.current_delay, Duration::from_secs(1));
+
+        config.reset_after = None;
+        state.attempts_remaining = Some(1);
+        state.attempt_finished(&config, Duration::from_secs(3600));
+        assert_eq!(state.attempts_remaining, Some(1));
+    }
+
+    #[test]
+    fn exhausted_only_when_configured_retries_ran_out() {
+        let config = retry_config(RetryCondition::AnyNonZero);
+        let mut state = RetryState::new(&config);
+        assert!(!state.exhausted(&config, &exited(1)));
+
+        state.attempts_remaining = Some(0);
+        assert!(state.exhausted(&config, &exited(1)));
+        assert!(!state.exhausted(&config, &exited(0)));
+
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.times = None;
+        let state = RetryState::new(&config);
+        assert!(!state.exhausted(&config, &exited(1)));
+    }
+
+    #[test]
+    fn restart_limit_uses_a_sliding_window() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.limit = Some(RestartLimit {
+            burst: 3,
+            interval: Duration::from_secs(10),
+        });
+        let mut state = RetryState::new(&config);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut restart = |secs| {
+            let allowed = state.restart_allowed(&config, at(secs));
+            if allowed {
+                state.restarted(&config, at(secs));
+            }
+            allowed
         };
 
-        assert!(!state.should_retry(&config, Some(status(1))));
+        assert!(restart(0));
+        assert!(restart(4));
+        assert!(restart(8));
+        assert!(!restart(9));
+        assert!(restart(10));
+        assert!(!restart(13));
+        assert!(restart(14));
+
+        // A check made before a long delay is measured against when the delay
+        // ends, so restarts that will have left the window by then count as gone.
+        let mut state = RetryState::new(&config);
+        for secs in [0, 1, 2] {
+            state.restarted(&config, at(secs));
+        }
+        assert!(!state.restart_allowed(&config, at(9)));
+        assert!(state.restart_allowed(&config, at(9) + Duration::from_secs(2)));
+    }
+
+    fn delays(config: &RetryConfig, seed: u64, count: usize) -> Vec<Duration> {
+        let mut state = RetryState::with_seed(config, seed);
+        (0..count).map(|_| state.next_delay(config)).collect()
+    }
+
+    fn secs(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|s| Duration::from_secs(*s)).collect()
+    }
+
+    #[test]
+    fn strategies_grow_delay_up_to_max() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.max_delay = Duration::from_secs(10);
+
+        assert_eq!(delays(&config, 0, 3), secs(&[1, 1, 1]));
+
+        config.backoff = Backoff::Linear;
+        assert_eq!(delays(&config, 0, 4), secs(&[1, 2, 3, 4]));
+
+        config.backoff = Backoff::Exponential(2.0);
+        assert_eq!(delays(&config, 0, 6), secs(&[1, 2, 4, 8, 10, 10]));
+
+        config.backoff = Backoff::Exponential(3.0);
+        assert_eq!(delays(&config, 0, 4), secs(&[1, 3, 9, 10]));
+    }
+
+    #[test]
+    fn base_delay_above_max_is_not_capped() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.delay = Duration::from_secs(600);
+
+        assert_eq!(delays(&config, 0, 3), secs(&[600, 600, 600]));
+
+        config.backoff = Backoff::Exponential(2.0);
+        assert_eq!(delays(&config, 0, 3), secs(&[600, 600, 600]));
+
+        config.backoff = Backoff::Decorrelated;
+        assert_eq!(delays(&config, 0, 3), secs(&[600, 600, 600]));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds_and_follows_seed() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.delay = Duration::from_secs(8);
+        config.jitter = Jitter::Full;
+        let full = delays(&config, 42, 50);
+        assert!(full.iter().all(|d| *d <= Duration::from_secs(8)));
+        assert!(full.windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(full, delays(&config, 42, 50));
+        assert_ne!(full, delays(&config, 43, 50));
+
+        config.jitter = Jitter::Equal;
+        let equal = delays(&config, 42, 50);
+        assert!(
+            equal
+                .iter()
+                .all(|d| (Duration::from_secs(4)..=Duration::from_secs(8)).contains(d))
+        );
+    }
+
+    #[test]
+    fn decorrelated_delays_stay_between_base_and_max() {
+        let mut config = retry_config(RetryCondition::AnyNonZero);
+        config.backoff = Backoff::Decorrelated;
+        config.max_delay = Duration::from_secs(20);
+
+        let mut previous = config.delay;
+        for delay in delays(&config, 7, 100) {
+            assert!(delay >= config.delay && delay <= config.max_delay);
+            assert!(delay <= previous * 3);
+            previous = delay;
+        }
+        assert_eq!(delays(&config, 7, 10), delays(&config, 7, 10));
     }
 }
@@ -0,0 +1,252 @@
+use crate::error::{Error, Result};
+use std::fmt;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+#[cfg(unix)]
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("ABRT", libc::SIGABRT),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("WINCH", libc::SIGWINCH),
+];
+
+// Signals are not delivered on this platform; the numbers only identify them.
+#[cfg(not(unix))]
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", 1),
+    ("INT", 2),
+    ("QUIT", 3),
+    ("ILL", 4),
+    ("ABRT", 6),
+    ("FPE", 8),
+    ("KILL", 9),
+    ("USR1", 10),
+    ("SEGV", 11),
+    ("USR2", 12),
+    ("PIPE", 13),
+    ("ALRM", 14),
+    ("TERM", 15),
+    ("CHLD", 17),
+    ("CONT", 18),
+    ("STOP", 19),
+    ("TSTP", 20),
+    ("TTIN", 21),
+    ("TTOU", 22),
+    ("WINCH", 28),
+];
+
+#[cfg(target_os = "linux")]
+fn max_signal() -> i32 {
+    libc::SIGRTMAX()
+}
+
+// No platform watchctl runs on has more than 64 signals.
+#[cfg(not(target_os = "linux"))]
+fn max_signal() -> i32 {
+    64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signal(i32);
+
+impl Signal {
+    #[cfg(unix)]
+    pub const KILL: Signal = Signal(libc::SIGKILL);
+    #[cfg(unix)]
+    pub const TERM: Signal = Signal(libc::SIGTERM);
+    #[cfg(not(unix))]
+    pub const KILL: Signal = Signal(9);
+    #[cfg(not(unix))]
+    pub const TERM: Signal = Signal(15);
+
+    pub fn number(self) -> i32 {
+        self.0
+    }
+
+    pub fn is_termination(self) -> bool {
+        matches!(self.name(), Some("TERM" | "INT"))
+    }
+
+    fn name(self) -> Option<&'static str> {
+        SIGNALS
+            .iter()
+            .find(|(_, n)| *n == self.0)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "SIG{name}"),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+pub fn parse_signal(s: &str) -> Result<Signal> {
+    let s = s.trim();
+
+    if let Ok(num) = s.parse::<i32>() {
+        if !(1..=max_signal()).contains(&num) {
+            return Err(Error::InvalidSignal(s.to_string()));
+        }
+        return Ok(Signal(num));
+    }
+
+    let upper = s.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, num)| Signal(*num))
+        .ok_or_else(|| Error::InvalidSignal(s.to_string()))
+}
+
+#[cfg(unix)]
+const FORWARDED: &[i32] = &[
+    libc::SIGTERM,
+    libc::SIGINT,
+    libc::SIGHUP,
+    libc::SIGUSR1,
+    libc::SIGUSR2,
+    libc::SIGWINCH,
+];
+
+pub struct SignalForwarder {
+    rx: mpsc::UnboundedReceiver<Signal>,
+    terminating: Option<Signal>,
+}
+
+impl SignalForwarder {
+    #[cfg(unix)]
+    pub fn install() -> Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        for &num in FORWARDED {
+            let mut stream = signal(SignalKind::from_raw(num))?;
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                while stream.recv().await.is_some() {
+                    if tx.send(Signal(num)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            rx,
+            terminating: None,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn install() -> Result<Self> {
+        let (_, rx) = mpsc::unbounded_channel();
+        Ok(Self {
+            rx,
+            terminating: None,
+        })
+    }
+
+    // Signals sent on the returned channel are handled as if received.
+    #[cfg(all(test, unix))]
+    pub fn channel() -> (mpsc::UnboundedSender<Signal>, Self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (
+            tx,
+            Self {
+                rx,
+                terminating: None,
+            },
+        )
+    }
+
+    pub async fn recv(&mut self) -> Signal {
+        let Some(signal) = self.rx.recv().await else {
+            return std::future::pending().await;
+        };
+
+        debug!("received {signal}");
+        if signal.is_termination() && self.terminating.is_none() {
+            self.terminating = Some(signal);
+        }
+        signal
+    }
+
+    pub async fn recv_termination(&mut self) -> Signal {
+        loop {
+            let signal = self.recv().await;
+            if signal.is_termination() {
+                return signal;
+            }
+        }
+    }
+
+    pub fn terminating(&self) -> Option<Signal> {
+        self.terminating
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(
+            parse_signal("SIGTERM").unwrap(),
+            parse_signal("TERM").unwrap()
+        );
+        assert_eq!(parse_signal("SIGTERM").unwrap().to_string(), "SIGTERM");
+        assert_eq!(parse_signal("sigkill").unwrap(), Signal::KILL);
+    }
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_signal("9").unwrap(), Signal::KILL);
+        assert!(parse_signal("0").is_err());
+        assert!(parse_signal("-1").is_err());
+        assert_eq!(parse_signal("64").unwrap().number(), 64);
+        assert!(parse_signal("65").is_err());
+        assert!(parse_signal("1000").is_err());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_signal("").is_err());
+        assert!(parse_signal("SIGNOPE").is_err());
+    }
+
+    #[test]
+    fn only_term_and_int_terminate() {
+        assert!(parse_signal("SIGTERM").unwrap().is_termination());
+        assert!(parse_signal("SIGINT").unwrap().is_termination());
+        assert!(!parse_signal("SIGHUP").unwrap().is_termination());
+        assert!(!parse_signal("SIGWINCH").unwrap().is_termination());
+    }
+
+    #[test]
+    fn display_uses_canonical_name() {
+        assert_eq!(parse_signal("int").unwrap().to_string(), "SIGINT");
+    }
+}
@@ -1,8 +1,14 @@
-use crate::check::{Check, FileCheck, HttpCheck, TcpCheck, build_http_client};
+#[cfg(unix)]
+use crate::check::UnixSocketCheck;
+use crate::check::{
+    Check, ExecCheck, FileCheck, GrpcCheck, HttpCheck, PostgresCheck, RedisCheck, TcpCheck,
+    build_http_client,
+};
 use crate::config::WaitConfig;
 use crate::error::{Error, Result};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::select;
 use tokio::time::{Instant, sleep, timeout};
 use tracing::{debug, info};
 
@@ -10,27 +16,72 @@ pub async fn run_wait_phase(config: &WaitConfig) -> Result<()> {
     let start = Instant::now();
     let deadline = start + config.timeout;
 
-    let mut checks: Vec<Box<dyn Check>> = Vec::new();
+    let mut checks: Vec<(Box<dyn Check>, Duration)> = Vec::new();
 
-    for addr in &config.tcp {
-        checks.push(Box::new(TcpCheck::new(addr.clone(), config.tcp_timeout)));
+    for tcp in &config.tcp {
+        checks.push((
+            Box::new(TcpCheck::new(tcp.addr.clone(), tcp.schedule.timeout)),
+            tcp.schedule.interval,
+        ));
     }
 
     let http_client = if !config.http.is_empty() {
-        Some(Arc::new(build_http_client(config.http_timeout)?))
+        Some(Arc::new(build_http_client()?))
     } else {
         None
     };
 
-    for url in &config.http {
-        checks.push(Box::new(HttpCheck::new(
-            url.clone(),
-            Arc::clone(http_client.as_ref().unwrap()),
-        )));
+    for http in &config.http {
+        checks.push((
+            Box::new(HttpCheck::new(
+                http.clone(),
+                Arc::clone(http_client.as_ref().unwrap()),
+            )),
+            http.schedule.interval,
+        ));
     }
 
-    for path in &config.files {
-        checks.push(Box::new(FileCheck::new(path)));
+    for file in &config.files {
+        checks.push((
+            Box::new(FileCheck::new(file.clone())),
+            file.schedule.interval,
+        ));
+    }
+
+    for exec in &config.exec {
+        checks.push((
+            Box::new(ExecCheck::new(exec.command.clone(), exec.schedule.timeout)),
+            exec.schedule.interval,
+        ));
+    }
+
+    #[cfg(unix)]
+    for unix in &config.unix {
+        checks.push((
+            Box::new(UnixSocketCheck::new(unix.clone())),
+            unix.schedule.interval,
+        ));
+    }
+
+    for postgres in &config.postgres {
+        checks.push((
+            Box::new(PostgresCheck::new(postgres.clone())),
+            postgres.schedule.interval,
+        ));
+    }
+
+    for redis in &config.redis {
+        checks.push((
+            Box::new(RedisCheck::new(redis.clone())),
+            redis.schedule.interval,
+        ));
+    }
+
+    for grpc in &config.grpc {
+        checks.push((
+            Box::new(GrpcCheck::new(grpc.clone())),
+            grpc.schedule.interval,
+        ));
     }
 
     if checks.is_empty() && config.delays.is_empty() {
@@ -52,7 +103,7 @@ pub async fn run_wait_phase(config: &WaitConfig) -> Result<()> {
 
     let check_futures: Vec<_> = checks
         .iter()
-        .map(|c| wait_for_check(c.as_ref(), deadline))
+        .map(|(c, poll_interval)| wait_for_check(c.as_ref(), deadline, *poll_interval))
         .collect();
 
     let results = futures::future::join_all(check_futures).await;
@@ -65,9 +116,14 @@ pub async fn run_wait_phase(config: &WaitConfig) -> Result<()> {
     Ok(())
 }
 
-async fn wait_for_check(check: &dyn Check, deadline: Instant) -> Result<()> {
+async fn wait_for_check(
+    check: &dyn Check,
+    deadline: Instant,
+    poll_interval: Duration,
+) -> Result<()> {
     let desc = check.description();
     debug!("waiting for {desc}");
+    let mut last_reason = None;
 
     loop {
         let remaining = deadline.saturating_duration_since(Instant::now());
@@ -81,14 +137,22 @@ async fn wait_for_check(check: &dyn Check, deadline: Instant) -> Result<()> {
                 return Ok(());
             }
             Ok(Err(e)) => {
-                debug!("{desc} not ready: {e}");
+                if last_reason.as_ref() == Some(&e) {
+                    debug!("{desc} not ready: {e}");
+                } else {
+                    info!("{desc} not ready: {e}");
+                    last_reason = Some(e);
+                }
             }
             Err(_) => {
                 return Err(Error::WaitTimeout);
             }
         }
 
-        let retry_delay = Duration::from_millis(500).min(remaining);
-        sleep(retry_delay).await;
+        let retry_delay = poll_interval.min(remaining);
+        select! {
+            _ = sleep(retry_delay) => {}
+            _ = check.changed() => debug!("{desc} changed"),
+        }
     }
 }
@@ -1,7 +1,14 @@
-use crate::check::{Check, FileCheck, HttpCheck, TcpCheck, build_http_client};
-use crate::config::WatchConfig;
+#[cfg(unix)]
+use crate::check::UnixSocketCheck;
+use crate::check::{
+    Check, ExecCheck, FileCheck, GrpcCheck, HttpCheck, PostgresCheck, RedisCheck, TcpCheck,
+    build_http_client,
+};
+use crate::config::{Schedule, Thresholds, WatchConfig};
 use crate::error::Result;
+use crate::output::{Patterns, Tee};
 use crate::process::Process;
+use crate::signal::{Signal, SignalForwarder};
 use std::process::ExitStatus;
 use std::sync::Arc;
 use std::time::Duration;
@@ -15,27 +22,65 @@ pub enum WatchResult {
     #[allow(dead_code)]
     HealthCheckFailed(String),
     Timeout,
+    OutputIdle,
 }
 
-pub async fn run_watch_phase(config: &WatchConfig, mut process: Process) -> Result<WatchResult> {
+pub async fn run_watch_phase(
+    config: &WatchConfig,
+    mut process: Process,
+    signals: &mut SignalForwarder,
+) -> Result<WatchResult> {
     let start = Instant::now();
 
-    let has_health_checks =
-        !config.http.is_empty() || !config.tcp.is_empty() || !config.files.is_empty();
+    let has_health_checks = !config.http.is_empty()
+        || !config.tcp.is_empty()
+        || !config.files.is_empty()
+        || !config.exec.is_empty()
+        || !config.unix.is_empty()
+        || !config.postgres.is_empty()
+        || !config.redis.is_empty()
+        || !config.grpc.is_empty();
 
     if !has_health_checks && config.timeout.is_none() {
         debug!("no watch conditions, waiting for process to exit");
-        let status = process.wait().await?;
-        return Ok(WatchResult::ProcessExited(status));
     }
 
+    let mut tee = process.take_output().map(|(stdout, stderr)| {
+        let patterns = Patterns {
+            ready: config.ready_pattern.clone(),
+            fail: config.fail_pattern.clone(),
+        };
+        Tee::spawn(stdout, stderr, patterns)
+    });
+    let ready = match (&config.ready_pattern, &mut tee) {
+        (Some(_), Some(tee)) => Some(tee.ready()),
+        _ => None,
+    };
+
+    let failed = tee.as_mut().map(Tee::failed);
+
     let watch_future = async {
-        if has_health_checks {
-            run_health_checks(config).await
-        } else {
+        let checks = async {
+            if let Some(ready) = ready {
+                ready.await;
+            }
+            if has_health_checks && let Err(msg) = run_health_checks(config).await {
+                return msg;
+            }
             std::future::pending().await
+        };
+        let failed = async {
+            match failed {
+                Some(failed) => failed.await,
+                None => std::future::pending().await,
+            }
+        };
+        select! {
+            msg = checks => msg,
+            line = failed => line,
         }
     };
+    tokio::pin!(watch_future);
 
     let timeout_future = async {
         if let Some(t) = config.timeout {
@@ -45,73 +90,147 @@ pub async fn run_watch_phase(config: &WatchConfig, mut process: Process) -> Resu
             std::future::pending().await
         }
     };
+    tokio::pin!(timeout_future);
 
-    select! {
-        status = process.wait() => {
-            let status = status?;
-            info!("process exited with {:?} after {:?}", status.code(), start.elapsed());
-            Ok(WatchResult::ProcessExited(status))
+    let idle_future = match (config.output_idle, &tee) {
+        (Some(limit), Some(tee)) => Some(tee.idle(limit)),
+        _ => None,
+    };
+    let idle_future = async {
+        match idle_future {
+            Some(idle) => idle.await,
+            None => std::future::pending().await,
         }
+    };
+    tokio::pin!(idle_future);
 
-        result = watch_future => {
-            match result {
-                Ok(()) => std::future::pending().await,
-                Err(msg) => {
-                    warn!("health check failed: {msg}");
-                    if let Err(e) = process.kill_and_wait().await {
-                        warn!("failed to kill process: {e}");
-                    }
-                    Ok(WatchResult::HealthCheckFailed(msg))
+    // Armed once a termination signal has been forwarded, so a command that
+    // ignores it is still killed after the stop timeout.
+    let kill_deadline = sleep(Duration::ZERO);
+    tokio::pin!(kill_deadline);
+    let mut kill_armed = false;
+
+    let result = loop {
+        let terminating = signals.terminating().is_some();
+
+        select! {
+            status = process.wait() => {
+                let status = status?;
+                info!("process exited with {:?} after {:?}", status.code(), start.elapsed());
+                break WatchResult::ProcessExited(status);
+            }
+
+            msg = &mut watch_future, if !terminating => {
+                warn!("health check failed: {msg}");
+                if let Err(e) = process.stop().await {
+                    warn!("failed to stop process: {e}");
                 }
+                break WatchResult::HealthCheckFailed(msg);
             }
-        }
 
-        _ = timeout_future => {
-            warn!("watch timeout reached after {:?}", start.elapsed());
-            if let Err(e) = process.kill_and_wait().await {
-                warn!("failed to kill process: {e}");
+            _ = &mut timeout_future, if !terminating => {
+                warn!("watch timeout reached after {:?}", start.elapsed());
+                if let Err(e) = process.stop().await {
+                    warn!("failed to stop process: {e}");
+                }
+                break WatchResult::Timeout;
+            }
+
+            _ = &mut idle_future, if !terminating => {
+                let limit = config.output_idle.unwrap_or_default();
+                warn!("no output from process for {limit:?}");
+                if let Err(e) = process.stop().await {
+                    warn!("failed to stop process: {e}");
+                }
+                break WatchResult::OutputIdle;
+            }
+
+            _ = &mut kill_deadline, if kill_armed => {
+                kill_armed = false;
+                warn!(
+                    "process did not exit within {:?} after a forwarded signal, sending {}",
+                    process.stop_timeout(),
+                    Signal::KILL
+                );
+                if let Err(e) = process.signal(Signal::KILL) {
+                    warn!("failed to kill process: {e}");
+                }
+            }
+
+            signal = signals.recv() => {
+                if let Err(e) = process.signal(signal) {
+                    warn!("failed to forward {signal}: {e}");
+                }
+                if signal.is_termination() && !kill_armed {
+                    info!("forwarded {signal}, waiting for process to exit");
+                    kill_deadline
+                        .as_mut()
+                        .reset(Instant::now() + process.stop_timeout());
+                    kill_armed = true;
+                }
             }
-            Ok(WatchResult::Timeout)
         }
+    };
+
+    if let Some(tee) = tee {
+        tee.finish().await;
     }
+    Ok(result)
 }
 
 async fn run_health_checks(config: &WatchConfig) -> std::result::Result<(), String> {
     let mut join_set = JoinSet::new();
 
     let http_client = if !config.http.is_empty() {
-        Some(Arc::new(
-            build_http_client(config.http_timeout).map_err(|e| e.to_string())?,
-        ))
+        Some(Arc::new(build_http_client().map_err(|e| e.to_string())?))
     } else {
         None
     };
 
-    for url in &config.http {
-        let check = HttpCheck::new(url.clone(), Arc::clone(http_client.as_ref().unwrap()));
-        let initial_delay = config.delay;
-        let interval_duration = config.http_interval;
-        join_set.spawn(async move {
-            run_periodic_check(Box::new(check), initial_delay, interval_duration).await
-        });
+    for http in &config.http {
+        let check = HttpCheck::new(http.clone(), Arc::clone(http_client.as_ref().unwrap()));
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, http.schedule);
     }
 
-    for addr in &config.tcp {
-        let check = TcpCheck::new(addr.clone(), config.tcp_timeout);
-        let initial_delay = config.delay;
-        let interval_duration = config.tcp_interval;
-        join_set.spawn(async move {
-            run_periodic_check(Box::new(check), initial_delay, interval_duration).await
-        });
+    for tcp in &config.tcp {
+        let check = TcpCheck::new(tcp.addr.clone(), tcp.schedule.timeout);
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, tcp.schedule);
     }
 
-    for path in &config.files {
-        let check = FileCheck::new(path);
-        let initial_delay = config.delay;
-        let interval_duration = config.file_interval;
-        join_set.spawn(async move {
-            run_periodic_check(Box::new(check), initial_delay, interval_duration).await
-        });
+    for file in &config.files {
+        let check = FileCheck::new(file.clone());
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, file.schedule);
+    }
+
+    for exec in &config.exec {
+        let check = ExecCheck::new(exec.command.clone(), exec.schedule.timeout);
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, exec.schedule);
+    }
+
+    for postgres in &config.postgres {
+        let check = PostgresCheck::new(postgres.clone());
+        spawn_periodic_check(
+            &mut join_set,
+            Box::new(check),
+            config.delay,
+            postgres.schedule,
+        );
+    }
+
+    for redis in &config.redis {
+        let check = RedisCheck::new(redis.clone());
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, redis.schedule);
+    }
+
+    for grpc in &config.grpc {
+        let check = GrpcCheck::new(grpc.clone());
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, grpc.schedule);
+    }
+
+    #[cfg(unix)]
+    for unix in &config.unix {
+        let check = UnixSocketCheck::new(unix.clone());
+        spawn_periodic_check(&mut join_set, Box::new(check), config.delay, unix.schedule);
     }
 
     while let Some(result) = join_set.join_next().await {
@@ -128,10 +247,22 @@ async fn run_health_checks(config: &WatchConfig) -> std::result::Result<(), Stri
     Ok(())
 }
 
+fn spawn_periodic_check(
+    join_set: &mut JoinSet<std::result::Result<(), String>>,
+    check: Box<dyn Check>,
+    initial_delay: Duration,
+    schedule: Schedule,
+) {
+    join_set.spawn(async move {
+        run_periodic_check(check, initial_delay, schedule.interval, schedule.thresholds).await
+    });
+}
+
 async fn run_periodic_check(
     check: Box<dyn Check>,
     initial_delay: Duration,
     interval_duration: Duration,
+    thresholds: Thresholds,
 ) -> std::result::Result<(), String> {
     if !initial_delay.is_zero() {
         sleep(initial_delay).await;
@@ -140,13 +271,50 @@ async fn run_periodic_check(
     let mut ticker = interval(interval_duration);
     ticker.tick().await;
 
+    let mut failures = 0;
+    let mut successes = 0;
+
     loop {
         let desc = check.description();
         match check.check().await {
-            Ok(()) => debug!("{desc} healthy"),
-            Err(msg) => return Err(msg),
+            Ok(()) if failures == 0 => debug!("{desc} healthy"),
+            Ok(()) => {
+                successes += 1;
+                if successes >= thresholds.success {
+                    info!("{desc} recovered after {failures} failures");
+                    failures = 0;
+                    successes = 0;
+                } else {
+                    info!(
+                        "{desc} succeeded ({successes}/{} to recover, {failures}/{} failures)",
+                        thresholds.success, thresholds.failure
+                    );
+                }
+            }
+            Err(msg) => {
+                failures += 1;
+                successes = 0;
+                warn!("{desc} failed ({failures}/{}): {msg}", thresholds.failure);
+                if failures >= thresholds.failure {
+                    return Err(msg);
+                }
+            }
+        }
+
+        // Only the ticker counts toward thresholds; an event fails the check
+        // right away when the change itself is fatal and is ignored otherwise.
+        loop {
+            select! {
+                _ = ticker.tick() => break,
+                change = check.changed() => match change {
+                    Some(msg) => {
+                        warn!("{desc} failed: {msg}");
+                        return Err(msg);
+                    }
+                    None => debug!("{desc} changed, checking at the next interval"),
+                },
+            }
         }
-        ticker.tick().await;
     }
 }
 
@@ -154,8 +322,14 @@ async fn run_periodic_check(
 mod tests {
     use super::*;
     use std::sync::Arc;
+    use std::sync::Mutex;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
+    const ONCE: Thresholds = Thresholds {
+        failure: 1,
+        success: 1,
+    };
+
     struct CountingCheck {
         calls: Arc<AtomicUsize>,
     }
@@ -174,6 +348,141 @@ mod tests {
         }
     }
 
+    struct ScriptedCheck {
+        results: Mutex<Vec<bool>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ScriptedCheck {
+        fn boxed(results: &[bool], calls: &Arc<AtomicUsize>) -> Box<dyn Check> {
+            let mut results = results.to_vec();
+            results.reverse();
+            Box::new(Self {
+                results: Mutex::new(results),
+                calls: Arc::clone(calls),
+            })
+        }
+    }
+
+    impl Check for ScriptedCheck {
+        fn check(&self) -> crate::check::CheckFuture<'_> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let healthy = self.results.lock().unwrap().pop().unwrap_or(true);
+            Box::pin(async move {
+                if healthy {
+                    Ok(())
+                } else {
+                    Err("scripted failure".to_string())
+                }
+            })
+        }
+
+        fn description(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    #[tokio::test]
+    async fn failure_threshold_requires_consecutive_failures() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let check = ScriptedCheck::boxed(&[false, false, true, false, false, false], &calls);
+        let thresholds = Thresholds {
+            failure: 3,
+            success: 1,
+        };
+
+        let result =
+            run_periodic_check(check, Duration::ZERO, Duration::from_millis(1), thresholds).await;
+
+        assert_eq!(result, Err("scripted failure".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn success_threshold_delays_failure_reset() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let check = ScriptedCheck::boxed(&[false, true, false, true, false], &calls);
+        let thresholds = Thresholds {
+            failure: 3,
+            success: 2,
+        };
+
+        let result =
+            run_periodic_check(check, Duration::ZERO, Duration::from_millis(1), thresholds).await;
+
+        assert_eq!(result, Err("scripted failure".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    // Always unhealthy, and reports a change every few milliseconds.
+    struct ChangingCheck {
+        change: Option<String>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Check for ChangingCheck {
+        fn check(&self) -> crate::check::CheckFuture<'_> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Err("down".to_string()) })
+        }
+
+        fn description(&self) -> &str {
+            "changing"
+        }
+
+        fn changed(&self) -> crate::check::ChangeFuture<'_> {
+            Box::pin(async {
+                sleep(Duration::from_millis(5)).await;
+                self.change.clone()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn change_events_do_not_count_toward_thresholds() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let check = Box::new(ChangingCheck {
+            change: None,
+            calls: Arc::clone(&calls),
+        });
+        let thresholds = Thresholds {
+            failure: 3,
+            success: 1,
+        };
+
+        let result = tokio::time::timeout(
+            Duration::from_millis(200),
+            run_periodic_check(check, Duration::ZERO, Duration::from_secs(60), thresholds),
+        )
+        .await;
+
+        assert!(result.is_err(), "events alone should not fail the check");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn fatal_change_fails_without_waiting_for_thresholds() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let check = Box::new(ChangingCheck {
+            change: Some("file /tmp/x does not exist".to_string()),
+            calls: Arc::clone(&calls),
+        });
+        let thresholds = Thresholds {
+            failure: 3,
+            success: 1,
+        };
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            run_periodic_check(check, Duration::ZERO, Duration::from_secs(60), thresholds),
+        )
+        .await
+        .expect("a fatal change should fail the check immediately");
+
+        assert_eq!(result, Err("file /tmp/x does not exist".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     async fn watch_delay_defers_first_probe() {
         let calls = Arc::new(AtomicUsize::new(0));
@@ -183,6 +492,7 @@ mod tests {
             }),
             Duration::from_millis(200),
             Duration::from_secs(60),
+            ONCE,
         ));
 
         sleep(Duration::from_millis(50)).await;
@@ -203,6 +513,7 @@ mod tests {
             }),
             Duration::ZERO,
             Duration::from_secs(60),
+            ONCE,
         ));
 
         tokio::time::timeout(Duration::from_millis(100), async {